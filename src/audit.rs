@@ -0,0 +1,558 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use colored::*;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::{
+    extended_version_req::ExtendedVersionReq,
+    graph::{DependencyGraph, format_path},
+    package::PackageKey,
+};
+
+/// How many dependency chains are shown per finding
+const MAX_PATHS: usize = 5;
+
+// The subset of the OSV schema (https://ossf.github.io/osv-schema/) used here
+#[derive(Debug, Deserialize)]
+struct OsvRecord {
+    id: String,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    affected: Vec<OsvAffected>,
+    #[serde(default)]
+    database_specific: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvAffected {
+    package: Option<OsvPackage>,
+    #[serde(default)]
+    ranges: Vec<OsvRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvPackage {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsvRange {
+    #[serde(rename = "type")]
+    range_type: String,
+    #[serde(default)]
+    events: Vec<HashMap<String, String>>,
+}
+
+/// An advisory for a single npm package
+#[derive(Debug, Clone, Serialize)]
+pub struct Advisory {
+    pub id: String,
+    pub summary: Option<String>,
+    pub severity: Option<String>,
+    #[serde(serialize_with = "serialize_display")]
+    pub affected: AffectedRanges,
+    pub affected_versions: Vec<Version>,
+    pub fixed: Vec<Version>,
+}
+
+fn serialize_display<S: serde::Serializer>(
+    value: &impl fmt::Display,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+impl Advisory {
+    pub fn affects(&self, version: &Version) -> bool {
+        self.affected_versions.contains(version) || self.affected.contains(version)
+    }
+
+    /// The first version after each affected range: the listed fixed versions,
+    /// and the next patch after a `last_affected` version
+    fn range_ends(&self) -> Vec<Version> {
+        let mut ends = self.fixed.clone();
+        ends.extend(self.affected.0.iter().filter_map(|range| {
+            let RangeEnd::LastAffected(last) = range.end.as_ref()? else {
+                return None;
+            };
+            Some(if last.pre.is_empty() {
+                Version::new(last.major, last.minor, last.patch + 1)
+            } else {
+                Version::new(last.major, last.minor, last.patch)
+            })
+        }));
+        ends
+    }
+}
+
+/// The affected ranges of an advisory. OSV versions are compared by semver
+/// precedence rather than as npm ranges, so pre-releases such as `1.0.0-rc.1`
+/// fall inside `>=0.0.0, <1.0.0`.
+#[derive(Debug, Clone, Default)]
+pub struct AffectedRanges(Vec<AffectedRange>);
+
+#[derive(Debug, Clone)]
+struct AffectedRange {
+    introduced: Version,
+    end: Option<RangeEnd>,
+}
+
+#[derive(Debug, Clone)]
+enum RangeEnd {
+    Fixed(Version),
+    LastAffected(Version),
+}
+
+impl AffectedRanges {
+    pub fn contains(&self, version: &Version) -> bool {
+        self.0.iter().any(|range| {
+            version.cmp_precedence(&range.introduced).is_ge()
+                && match &range.end {
+                    Some(RangeEnd::Fixed(fixed)) => version.cmp_precedence(fixed).is_lt(),
+                    Some(RangeEnd::LastAffected(last)) => version.cmp_precedence(last).is_le(),
+                    None => true,
+                }
+        })
+    }
+}
+
+impl fmt::Display for AffectedRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, range) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " || ")?;
+            }
+            write!(f, ">={}", range.introduced)?;
+            match &range.end {
+                Some(RangeEnd::Fixed(fixed)) => write!(f, ", <{}", fixed)?,
+                Some(RangeEnd::LastAffected(last)) => write!(f, ", <={}", last)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Advisories loaded from a local directory of OSV JSON files, by package name
+#[derive(Debug, Default)]
+pub struct AdvisoryDatabase {
+    advisories: HashMap<String, Vec<Advisory>>,
+}
+
+impl AdvisoryDatabase {
+    /// Loads every `.json` file below the folder. Files may hold a single OSV
+    /// record or an array of them; non-npm entries are ignored.
+    pub fn from_folder(folder: &Path) -> Result<Self> {
+        if !folder.is_dir() {
+            return Err(eyre!(
+                "Advisory database {} is not a folder",
+                folder.display()
+            ));
+        }
+
+        let mut database = Self::default();
+        let pattern = folder.join("**").join("*.json");
+        for path in glob::glob(&pattern.to_string_lossy())?.flatten() {
+            debug!("Loading advisories from {}", path.display());
+            let content = fs::read_to_string(&path)?;
+            let records = match serde_json::from_str::<Value>(&content)? {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            for record in records {
+                match serde_json::from_value::<OsvRecord>(record) {
+                    Ok(record) => database.add_record(record),
+                    Err(e) => warn!("Skipping invalid OSV record in {}: {}", path.display(), e),
+                }
+            }
+        }
+        Ok(database)
+    }
+
+    fn add_record(&mut self, record: OsvRecord) {
+        let severity = record
+            .database_specific
+            .as_ref()
+            .and_then(|d| d.get("severity"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string());
+
+        for affected in record.affected {
+            let Some(package) = affected.package else {
+                continue;
+            };
+            if !package.ecosystem.eq_ignore_ascii_case("npm") {
+                continue;
+            }
+
+            let mut ranges = Vec::new();
+            let mut fixed = Vec::new();
+            for range in affected
+                .ranges
+                .iter()
+                .filter(|r| r.range_type == "SEMVER" || r.range_type == "ECOSYSTEM")
+            {
+                let (affected_ranges, range_fixed) = events_to_ranges(&range.events);
+                ranges.extend(affected_ranges);
+                fixed.extend(range_fixed);
+            }
+            fixed.sort();
+            fixed.dedup();
+
+            self.advisories
+                .entry(package.name)
+                .or_default()
+                .push(Advisory {
+                    id: record.id.clone(),
+                    summary: record.summary.clone(),
+                    severity: severity.clone(),
+                    affected: AffectedRanges(ranges),
+                    affected_versions: affected
+                        .versions
+                        .iter()
+                        .filter_map(|v| Version::parse(v).ok())
+                        .collect(),
+                    fixed,
+                });
+        }
+    }
+
+    pub fn advisories_for(&self, name: &str) -> &[Advisory] {
+        self.advisories.get(name).map_or(&[], |a| a.as_slice())
+    }
+}
+
+/// Turns OSV `introduced`/`fixed`/`last_affected` events into affected
+/// ranges, returning them along with the fixed versions.
+fn events_to_ranges(events: &[HashMap<String, String>]) -> (Vec<AffectedRange>, Vec<Version>) {
+    let mut ranges = Vec::new();
+    let mut fixed = Vec::new();
+    let mut introduced: Option<Version> = None;
+
+    let parse = |version: &str| {
+        if version == "0" {
+            Some(Version::new(0, 0, 0))
+        } else {
+            Version::parse(version).ok()
+        }
+    };
+
+    for event in events {
+        if let Some(version) = event.get("introduced") {
+            introduced = parse(version);
+        } else if let Some(version) = event.get("fixed").and_then(|v| parse(v)) {
+            if let Some(introduced) = introduced.take() {
+                ranges.push(AffectedRange {
+                    introduced,
+                    end: Some(RangeEnd::Fixed(version.clone())),
+                });
+            }
+            fixed.push(version);
+        } else if let Some(version) = event.get("last_affected").and_then(|v| parse(v))
+            && let Some(introduced) = introduced.take()
+        {
+            ranges.push(AffectedRange {
+                introduced,
+                end: Some(RangeEnd::LastAffected(version)),
+            });
+        }
+    }
+    if let Some(introduced) = introduced {
+        ranges.push(AffectedRange {
+            introduced,
+            end: None,
+        });
+    }
+
+    (ranges, fixed)
+}
+
+/// Whether an affected package can be fixed without changing declared ranges
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Fix {
+    /// Reinstalling would pick up this version without changing any package.json.
+    /// Unless `listed` it isn't one of the advisory's fixed versions but the
+    /// first version outside its affected ranges, which may not be published.
+    WithinRanges { version: Version, listed: bool },
+    /// Versions outside the affected ranges exist, but some dependents'
+    /// ranges exclude them
+    RequiresRangeChange {
+        fixed: Vec<Version>,
+        blocking: Vec<PackageKey>,
+    },
+    /// Every version is affected
+    Unfixed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    pub advisory: Advisory,
+    pub fix: Fix,
+    pub paths: Vec<Vec<PackageKey>>,
+}
+
+impl fmt::Display for AuditFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = self
+            .advisory
+            .severity
+            .as_ref()
+            .map(|s| format!(" ({})", s))
+            .unwrap_or_default();
+        write!(
+            f,
+            "{} {}{}",
+            self.package,
+            self.advisory.id.red().bold(),
+            severity.red()
+        )?;
+        if let Some(summary) = &self.advisory.summary {
+            write!(f, "{} {}", ":".bright_black(), summary)?;
+        }
+
+        let fix = match &self.fix {
+            Fix::WithinRanges {
+                version,
+                listed: true,
+            } => format!("fixed in {}, which satisfies all dependents", version).green(),
+            Fix::WithinRanges {
+                version,
+                listed: false,
+            } => format!(
+                "{} is the first version outside the affected ranges that satisfies all dependents",
+                version
+            )
+            .green(),
+            Fix::RequiresRangeChange { fixed, blocking } => format!(
+                "outside the affected ranges from {}, but not allowed by {}",
+                join(fixed),
+                join(blocking)
+            )
+            .yellow(),
+            Fix::Unfixed => "no fixed version".red(),
+        };
+        write!(f, "\n  {}", fix)?;
+
+        for path in &self.paths {
+            write!(f, "\n  {}", format_path(path))?;
+        }
+        Ok(())
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Matches every package in the graph against the advisory database
+pub fn audit(graph: &DependencyGraph, database: &AdvisoryDatabase) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+
+    for package in graph.packages() {
+        let Some(version) = &package.version else {
+            continue;
+        };
+        let key = PackageKey::from(package.as_ref());
+
+        for advisory in database.advisories_for(&package.name) {
+            if !advisory.affects(version) {
+                continue;
+            }
+
+            findings.push(AuditFinding {
+                fix: find_fix(graph, &key, advisory),
+                paths: graph.paths_to(&key, MAX_PATHS),
+                install_path: package.data.install_path.clone(),
+                package: key.clone(),
+                advisory: advisory.clone(),
+            });
+        }
+    }
+
+    findings
+}
+
+/// Checks whether a version outside the advisory's affected ranges is
+/// accepted by the declared range of every package that depends on the
+/// affected package. Without a registry the published versions aren't known,
+/// so besides the listed fixed versions the candidates are where affected
+/// ranges end and where the dependents' ranges start, e.g. `1.4.0` for `^1.4.0` when only `<1.2.0` and `1.5.x` are
+/// affected.
+fn find_fix(graph: &DependencyGraph, key: &PackageKey, advisory: &Advisory) -> Fix {
+    // The same folder can be reached under several keys, so dependents are merged by path
+    let mut seen = HashSet::new();
+    let dependent_reqs = graph
+        .dependents(key)
+        .iter()
+        .filter_map(|dependent| {
+            let package = graph.get(dependent)?;
+            if !seen.insert(package.data.install_path.clone()) {
+                return None;
+            }
+            let dependency = package
+                .dependencies
+                .get(&key.name)
+                .or_else(|| package.dev_dependencies.get(&key.name))?;
+            Some((dependent.clone(), dependency.version_req.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut fixed = advisory.range_ends();
+    fixed.extend(
+        dependent_reqs
+            .iter()
+            .filter_map(|(_, req)| req.min_version()),
+    );
+    fixed.retain(|version| !advisory.affects(version));
+    fixed.sort();
+    fixed.dedup();
+    if fixed.is_empty() {
+        return Fix::Unfixed;
+    }
+
+    // Requirements that can't be checked (e.g. tags or workspace:) don't block a fix
+    let accepts = |req: &ExtendedVersionReq, version: &Version| req.matches(version) != Some(false);
+    if let Some(version) = fixed
+        .iter()
+        .find(|version| dependent_reqs.iter().all(|(_, req)| accepts(req, version)))
+    {
+        return Fix::WithinRanges {
+            version: version.clone(),
+            listed: advisory.fixed.contains(version),
+        };
+    }
+
+    let latest_fix = fixed.last().expect("fixed is not empty");
+    Fix::RequiresRangeChange {
+        blocking: dependent_reqs
+            .into_iter()
+            .filter(|(_, req)| !accepts(req, latest_fix))
+            .map(|(dependent, _)| dependent)
+            .collect(),
+        fixed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osv_ranges() {
+        let record: OsvRecord = serde_json::from_str(
+            r#"{
+                "id": "GHSA-test",
+                "summary": "Prototype pollution",
+                "database_specific": { "severity": "HIGH" },
+                "affected": [{
+                    "package": { "ecosystem": "npm", "name": "lodash" },
+                    "ranges": [{
+                        "type": "SEMVER",
+                        "events": [
+                            { "introduced": "0" }, { "fixed": "4.17.12" },
+                            { "introduced": "4.17.15" }, { "last_affected": "4.17.20" }
+                        ]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let mut database = AdvisoryDatabase::default();
+        database.add_record(record);
+
+        let advisory = &database.advisories_for("lodash")[0];
+        assert_eq!(advisory.severity.as_deref(), Some("HIGH"));
+        assert_eq!(advisory.fixed, vec![Version::new(4, 17, 12)]);
+        assert!(advisory.affects(&Version::new(3, 0, 0)));
+        assert!(!advisory.affects(&Version::new(4, 17, 12)));
+        assert!(advisory.affects(&Version::new(4, 17, 20)));
+        assert!(!advisory.affects(&Version::new(4, 17, 21)));
+
+        // Pre-releases inside a range are affected, and ones before its
+        // introduced version aren't
+        assert!(advisory.affects(&Version::parse("4.17.12-rc.1").unwrap()));
+        assert!(advisory.affects(&Version::parse("4.17.20-beta.1").unwrap()));
+        assert!(!advisory.affects(&Version::parse("4.17.15-beta.1").unwrap()));
+        assert_eq!(
+            advisory.affected.to_string(),
+            ">=0.0.0, <4.17.12 || >=4.17.15, <=4.17.20"
+        );
+    }
+
+    #[test]
+    fn test_find_fix() {
+        let mut resolver = crate::resolver::Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/cycles")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let x = graph
+            .packages()
+            .map(|package| PackageKey::from(package.as_ref()))
+            .find(|key| key.name == "x")
+            .unwrap();
+
+        let fix = |events: &str| {
+            let record: OsvRecord = serde_json::from_str(&format!(
+                r#"{{
+                    "id": "GHSA-test",
+                    "affected": [{{
+                        "package": {{ "ecosystem": "npm", "name": "x" }},
+                        "ranges": [{{ "type": "SEMVER", "events": {} }}]
+                    }}]
+                }}"#,
+                events
+            ))
+            .unwrap();
+            let mut database = AdvisoryDatabase::default();
+            database.add_record(record);
+            find_fix(&graph, &x, &database.advisories_for("x")[0])
+        };
+
+        // x@1.0.0 is required as ^1.0.0 by a and y. No fixed version is
+        // listed, but the versions after last_affected aren't affected.
+        match fix(r#"[{ "introduced": "0" }, { "last_affected": "1.0.0" }]"#) {
+            Fix::WithinRanges { version, listed } => {
+                assert_eq!(version, Version::new(1, 0, 1));
+                assert!(!listed);
+            }
+            fix => panic!("unexpected fix {:?}", fix),
+        }
+        match fix(
+            r#"[{ "introduced": "0" }, { "fixed": "1.0.0-rc.1" }, { "introduced": "1.0.0" }, { "fixed": "1.1.0" }]"#,
+        ) {
+            Fix::WithinRanges { version, listed } => {
+                assert_eq!(version, Version::new(1, 1, 0));
+                assert!(listed);
+            }
+            fix => panic!("unexpected fix {:?}", fix),
+        }
+        match fix(r#"[{ "introduced": "0" }, { "fixed": "2.0.0" }]"#) {
+            Fix::RequiresRangeChange { fixed, blocking } => {
+                assert_eq!(fixed, vec![Version::new(2, 0, 0)]);
+                let mut blocking = blocking.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+                blocking.sort();
+                assert_eq!(blocking, vec!["a@1.0.0", "y@1.2.0"]);
+            }
+            fix => panic!("unexpected fix {:?}", fix),
+        }
+        assert!(matches!(fix(r#"[{ "introduced": "0" }]"#), Fix::Unfixed));
+    }
+}
@@ -0,0 +1,387 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use colored::*;
+use semver::Version;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    engines::EngineViolation,
+    graph::DependencyGraph,
+    json_positions::Position,
+    licenses::LicenseViolation,
+    package::{PackageEntry, PackageKey},
+    package_data::read_package_json,
+    ptree_impl::sorted_values,
+};
+
+/// Something `check` considers wrong with an installed package
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// The installed version doesn't satisfy the declared range
+    Mismatch {
+        dependency: String,
+        required: String,
+        found: PackageKey,
+    },
+    /// No version of the dependency is installed
+    Missing {
+        dependency: String,
+        required: String,
+    },
+    /// A peer dependency is missing or its installed version doesn't satisfy the declared range
+    Peer {
+        dependency: String,
+        required: String,
+        /// The installed version, or None if no version is installed
+        found: Option<String>,
+    },
+    /// The package's `engines.node` excludes the target Node version
+    Engine { required: String, node: String },
+    /// The package's license violates the license policy
+    License {
+        license: Option<String>,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// The package that declares the dependency (or has the license)
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    /// Whether the dependency was declared in devDependencies
+    pub dev: bool,
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// Where the offending entry is in the package's package.json
+    #[serde(skip)]
+    pub position: Option<Position>,
+}
+
+impl Finding {
+    /// The package.json keys of the offending entry, e.g. `["dependencies", "react"]`
+    pub fn json_path(&self) -> Vec<&str> {
+        match &self.problem {
+            Problem::Mismatch { dependency, .. } | Problem::Missing { dependency, .. } => {
+                let section = if self.dev {
+                    "devDependencies"
+                } else {
+                    "dependencies"
+                };
+                vec![section, dependency]
+            }
+            Problem::Peer { dependency, .. } => vec!["peerDependencies", dependency],
+            Problem::Engine { .. } => vec!["engines", "node"],
+            Problem::License { .. } => vec!["license"],
+        }
+    }
+
+    /// The problem as a sentence, without the terminal colors of Display
+    pub fn message(&self) -> String {
+        let package = &self.package;
+        let kind = if self.dev {
+            "dev dependency"
+        } else {
+            "dependency"
+        };
+        match &self.problem {
+            Problem::Mismatch {
+                dependency,
+                required,
+                found,
+            } => format!(
+                "{} requires {} {}@{}, but {} is installed",
+                package,
+                kind,
+                dependency,
+                required,
+                found.version_str()
+            ),
+            Problem::Missing {
+                dependency,
+                required,
+            } => format!(
+                "{} requires {} {}@{}, but it is not installed",
+                package, kind, dependency, required
+            ),
+            Problem::Peer {
+                dependency,
+                required,
+                found: Some(version),
+            } => format!(
+                "{} requires peer dependency {}@{}, but {} is installed",
+                package, dependency, required, version
+            ),
+            Problem::Peer {
+                dependency,
+                required,
+                found: None,
+            } => format!(
+                "{} requires peer dependency {}@{}, but it is not installed",
+                package, dependency, required
+            ),
+            Problem::Engine { required, node } => {
+                format!("{} requires node {}, not {}", package, required, node)
+            }
+            Problem::License { reason, .. } => format!("{}: {}", package, reason),
+        }
+    }
+
+    pub fn from_engine_violation(violation: EngineViolation, node: &Version) -> Self {
+        Self {
+            package: violation.package,
+            install_path: violation.install_path,
+            dev: false,
+            problem: Problem::Engine {
+                required: violation.required,
+                node: node.to_string(),
+            },
+            position: None,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Problem::Mismatch {
+                dependency,
+                required,
+                found,
+            } => write!(
+                f,
+                "{} {} {}{}{} {} {}",
+                self.package,
+                ">".bright_black(),
+                dependency,
+                "@".bright_black(),
+                required.bright_blue(),
+                ":".bright_black(),
+                (found.version_str() + " (version not satisfied)")
+                    .red()
+                    .bold()
+            ),
+            Problem::Missing {
+                dependency,
+                required,
+            } => write!(
+                f,
+                "{} {} {}{}{} {} {}",
+                self.package,
+                ">".bright_black(),
+                dependency,
+                "@".bright_black(),
+                required.bright_blue(),
+                ":".bright_black(),
+                "[MISSING]".red()
+            ),
+            Problem::Peer {
+                dependency,
+                required,
+                found,
+            } => write!(
+                f,
+                "{} {} {}{}{} {} {} {}",
+                self.package,
+                ">".bright_black(),
+                dependency,
+                "@".bright_black(),
+                required.bright_blue(),
+                "(peer)".bright_black(),
+                ":".bright_black(),
+                match found {
+                    Some(version) => (version.clone() + " (version not satisfied)").red().bold(),
+                    None => "[MISSING]".red(),
+                }
+            ),
+            Problem::Engine { required, node } => write!(
+                f,
+                "{}{} {}",
+                self.package,
+                ":".bright_black(),
+                format!("requires node {}, not {}", required, node).red()
+            ),
+            Problem::License { reason, .. } => {
+                write!(f, "{}{} {}", self.package, ":".bright_black(), reason.red())
+            }
+        }
+    }
+}
+
+impl From<LicenseViolation> for Finding {
+    fn from(violation: LicenseViolation) -> Self {
+        Self {
+            package: violation.package.package,
+            install_path: violation.package.install_path,
+            dev: false,
+            problem: Problem::License {
+                license: violation.package.license.expression,
+                reason: violation.reason,
+            },
+            position: None,
+        }
+    }
+}
+
+/// Finds every declared dependency in the graph that is missing or whose
+/// installed version doesn't satisfy the declared range.
+pub fn check_dependencies(graph: &DependencyGraph) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for package in graph.packages() {
+        let key = PackageKey::from(package.as_ref());
+        let dependencies = sorted_values(&package.dependencies)
+            .into_iter()
+            .map(|d| (d, false))
+            .chain(
+                sorted_values(&package.dev_dependencies)
+                    .into_iter()
+                    .map(|d| (d, true)),
+            );
+
+        for (dependency, dev) in dependencies {
+            let problem = match &dependency.package {
+                PackageEntry::Resolved(found) => {
+                    let satisfied = found
+                        .version
+                        .as_ref()
+                        .and_then(|version| dependency.version_req.matches(version));
+                    if satisfied != Some(false) {
+                        continue;
+                    }
+                    Problem::Mismatch {
+                        dependency: dependency.name.clone(),
+                        required: dependency.version_req.to_string(),
+                        found: found.clone(),
+                    }
+                }
+                PackageEntry::Missing => Problem::Missing {
+                    dependency: dependency.name.clone(),
+                    required: dependency.version_req.to_string(),
+                },
+                PackageEntry::Truncated => continue,
+            };
+
+            findings.push(Finding {
+                package: key.clone(),
+                install_path: package.data.install_path.clone(),
+                dev,
+                problem,
+                position: None,
+            });
+        }
+    }
+
+    findings
+}
+
+/// Finds every peer dependency in the graph that is missing (unless marked
+/// optional) or whose installed version doesn't satisfy the declared range.
+/// Peers are looked up the way Node would from the package's folder.
+pub fn check_peers(graph: &DependencyGraph) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for package in graph.packages() {
+        for (name, required) in sorted_entries(&package.data.peer_dependencies) {
+            let found = find_installed_version(&package.data.install_path, name);
+            let satisfied = match &found {
+                Some(Some(version)) => required.matches(version) != Some(false),
+                // Installed without a version, so there is nothing to compare
+                Some(None) => true,
+                None => package.data.optional_peers.contains(name),
+            };
+            if satisfied {
+                continue;
+            }
+
+            findings.push(Finding {
+                package: PackageKey::from(package.as_ref()),
+                install_path: package.data.install_path.clone(),
+                dev: false,
+                problem: Problem::Peer {
+                    dependency: name.clone(),
+                    required: required.to_string(),
+                    found: found.flatten().map(|version| version.to_string()),
+                },
+                position: None,
+            });
+        }
+    }
+
+    findings
+}
+
+/// Fills in where each finding's entry is in its package.json
+pub fn locate(findings: &mut [Finding], graph: &DependencyGraph) {
+    for finding in findings {
+        finding.position = graph
+            .get(&finding.package)
+            .and_then(|package| package.data.positions.get(&finding.json_path()));
+    }
+}
+
+fn sorted_entries<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(name, _)| name.as_str());
+    entries
+}
+
+/// The version of `name` that Node would load from `folder`: Some(None) if
+/// it is installed without a version, None if it isn't installed. Folders
+/// with an unreadable package.json are skipped.
+fn find_installed_version(folder: &Path, name: &str) -> Option<Option<Version>> {
+    for ancestor in folder.ancestors() {
+        // Node doesn't look in node_modules/node_modules
+        if ancestor.file_name().is_some_and(|n| n == "node_modules") {
+            continue;
+        }
+        let manifest = ancestor
+            .join("node_modules")
+            .join(name)
+            .join("package.json");
+        match read_package_json(&manifest) {
+            Ok(Some((value, _))) => {
+                return Some(
+                    value
+                        .get("version")
+                        .and_then(|v| v.as_str())
+                        .and_then(|v| Version::parse(v).ok()),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Skipping invalid {}: {}", manifest.display(), e),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_check_peers() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/check")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+
+        // plugin's own node_modules/host/package.json is invalid, so the
+        // hoisted host is found instead. The optional peer isn't installed.
+        let findings = check_peers(&graph);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].package.to_string(), "plugin@1.0.0");
+        assert_eq!(
+            findings[0].message(),
+            "plugin@1.0.0 requires peer dependency host@^2.0.0, but 1.0.0 is installed"
+        );
+    }
+}
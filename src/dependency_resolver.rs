@@ -1,6 +1,8 @@
-use std::{cell::RefCell, collections::HashMap, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, path::PathBuf, rc::Rc};
 
 use color_eyre::eyre::{Result, eyre};
+use colored::*;
+use serde::Serialize;
 
 use crate::{
     extended_version_req::ExtendedVersionReq,
@@ -16,6 +18,34 @@ pub struct DependencyResolver {
     packages: RefCell<HashMap<PackageKey, Rc<Package>>>,
     visiting: RefCell<Vec<PackageKey>>,
     current_depth: RefCell<usize>,
+    cycles: RefCell<Vec<Vec<PackageKey>>>,
+}
+
+/// A dependency cycle detected during resolution. Each package depends on the
+/// next one, and the last package depends on the first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cycle {
+    pub packages: Vec<PackageKey>,
+    /// True if every package in the cycle is a workspace member (or root)
+    pub workspace_only: bool,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain = self
+            .packages
+            .iter()
+            .chain(self.packages.first())
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(&" -> ".bright_black().to_string());
+
+        if self.workspace_only {
+            write!(f, "{} {}", "[WORKSPACE]".blue(), chain)
+        } else {
+            write!(f, "{}", chain)
+        }
+    }
 }
 
 impl DependencyResolver {
@@ -26,6 +56,7 @@ impl DependencyResolver {
             packages: RefCell::new(HashMap::new()),
             visiting: RefCell::new(Vec::new()),
             current_depth: RefCell::new(0),
+            cycles: RefCell::new(Vec::new()),
         })
     }
 
@@ -51,7 +82,13 @@ impl DependencyResolver {
 
         let key = package_data.into();
 
-        if self.packages.borrow().contains_key(&key) || self.visiting.borrow().contains(&key) {
+        let cycle_start = self.visiting.borrow().iter().position(|k| k == &key);
+        if let Some(start) = cycle_start {
+            self.record_cycle(self.visiting.borrow()[start..].to_vec());
+            return Ok(PackageEntry::Resolved(key));
+        }
+
+        if self.packages.borrow().contains_key(&key) {
             return Ok(PackageEntry::Resolved(key));
         }
 
@@ -71,11 +108,11 @@ impl DependencyResolver {
             if node_modules_path.exists() {
                 let sub_resolver = node_modules.create_child(install_path.clone())?;
 
-                resolved_dependencies = self.resolve_deps(&dependencies, &sub_resolver)?;
-                resolved_dev_dependencies = self.resolve_deps(&dev_dependencies, &sub_resolver)?;
+                resolved_dependencies = self.resolve_deps(dependencies, &sub_resolver)?;
+                resolved_dev_dependencies = self.resolve_deps(dev_dependencies, &sub_resolver)?;
             } else {
-                resolved_dependencies = self.resolve_deps(&dependencies, node_modules)?;
-                resolved_dev_dependencies = self.resolve_deps(&dev_dependencies, node_modules)?;
+                resolved_dependencies = self.resolve_deps(dependencies, node_modules)?;
+                resolved_dev_dependencies = self.resolve_deps(dev_dependencies, node_modules)?;
             }
 
             *self.current_depth.borrow_mut() -= 1;
@@ -88,6 +125,7 @@ impl DependencyResolver {
             dependencies: resolved_dependencies,
             dev_dependencies: resolved_dev_dependencies,
             visited: RefCell::new(false),
+            deprecated: RefCell::new(None),
             dep_resolver: Rc::downgrade(self),
             data: package_data.clone(),
         };
@@ -130,8 +168,37 @@ impl DependencyResolver {
         Ok(packages)
     }
 
+    fn record_cycle(&self, mut cycle: Vec<PackageKey>) {
+        // Rotate the cycle so the same loop entered from a different package
+        // is only recorded once
+        let first = (0..cycle.len())
+            .min_by_key(|&i| (&cycle[i].name, &cycle[i].version))
+            .unwrap_or(0);
+        cycle.rotate_left(first);
+
+        let mut cycles = self.cycles.borrow_mut();
+        if !cycles.contains(&cycle) {
+            cycles.push(cycle);
+        }
+    }
+
+    /// Returns the cycles detected so far.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        self.cycles
+            .borrow()
+            .iter()
+            .map(|packages| Cycle {
+                packages: packages.clone(),
+                workspace_only: packages.iter().all(|key| {
+                    self.get_package(key)
+                        .is_some_and(|package| package.data.is_first_party())
+                }),
+            })
+            .collect()
+    }
+
     pub fn get_package(&self, key: &PackageKey) -> Option<Rc<Package>> {
-        self.packages.borrow().get(key).map(|r| r.clone())
+        self.packages.borrow().get(key).cloned()
     }
 
     pub fn unwrap_entry(self: &Rc<Self>, entry: PackageEntry) -> Result<Rc<Package>> {
@@ -160,3 +227,38 @@ impl DependencyResolver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_cycles() {
+        let path = Path::new("tests/cycles");
+        let mut resolver = Resolver::new(usize::MAX);
+        let root = resolver.resolve(path).unwrap();
+        let workspace_data = root.data.workspace_data.clone().unwrap();
+        resolver
+            .resolve_workspace_members(path, &workspace_data)
+            .unwrap();
+
+        let mut cycles = root.resolver().unwrap().cycles();
+        cycles.sort_by_key(|cycle| cycle.packages[0].name.clone());
+
+        let names = cycles
+            .iter()
+            .map(|cycle| {
+                cycle
+                    .packages
+                    .iter()
+                    .map(|key| key.name.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![vec!["a", "b"], vec!["x", "y"]]);
+        assert!(cycles[0].workspace_only);
+        assert!(!cycles[1].workspace_only);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{fmt, path::PathBuf};
+
+use color_eyre::eyre::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::{
+    graph::{DependencyGraph, format_path},
+    package::PackageKey,
+    registry::Registry,
+};
+
+/// How many dependency chains are shown per deprecated package
+const MAX_PATHS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecatedPackage {
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    pub message: String,
+    pub paths: Vec<Vec<PackageKey>>,
+}
+
+impl fmt::Display for DeprecatedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}{} {}",
+            self.package,
+            "[DEPRECATED]".yellow(),
+            ":".bright_black(),
+            self.message
+        )?;
+        for path in &self.paths {
+            write!(f, "\n  {}", format_path(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Looks up every installed package version in the registry and marks the
+/// deprecated ones, so the tree shows them with a `[DEPRECATED]` badge.
+/// Packages without registry metadata (including first-party ones) are skipped.
+pub fn find_deprecated(
+    graph: &DependencyGraph,
+    registry: &Registry,
+) -> Result<Vec<DeprecatedPackage>> {
+    let mut deprecated = Vec::new();
+
+    for package in graph.packages() {
+        if package.data.is_first_party() {
+            continue;
+        }
+        let Some(version) = &package.version else {
+            continue;
+        };
+        let Some(packument) = registry.packument(&package.name)? else {
+            continue;
+        };
+        let Some(message) = packument
+            .versions
+            .get(&version.to_string())
+            .and_then(|v| v.deprecation())
+        else {
+            continue;
+        };
+
+        package.deprecated.replace(Some(message.to_string()));
+
+        let key = PackageKey::from(package.as_ref());
+        deprecated.push(DeprecatedPackage {
+            paths: graph.paths_to(&key, MAX_PATHS),
+            install_path: package.data.install_path.clone(),
+            message: message.to_string(),
+            package: key,
+        });
+    }
+
+    Ok(deprecated)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ptree::PrintConfig;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_find_deprecated() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/registry")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+
+        // Only installed versions count, and `"deprecated": false` is a lifted deprecation
+        let registry = Registry::open("tests/registry/packuments").unwrap();
+        let mut deprecated = find_deprecated(&graph, &registry)
+            .unwrap()
+            .into_iter()
+            .map(|d| format!("{}: {}", d.package, d.message))
+            .collect::<Vec<_>>();
+        deprecated.sort();
+        assert_eq!(
+            deprecated,
+            vec![
+                "@scope/gone@1.0.0: No longer maintained",
+                "old@1.0.0: Use old@2 instead"
+            ]
+        );
+
+        colored::control::set_override(false);
+        let mut out = Vec::new();
+        roots[0]
+            .write_tree(&mut out, &PrintConfig::default())
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "registry-fixture@1.0.0
+├─ @scope/gone@^1.0.0 : 1.0.0 [DEPRECATED]
+├─ fresh@^1.1.0 : 1.1.0
+├─ lifted@^1.0.0 : 1.0.0
+├─ local@file:local : 1.0.0
+├─ member@workspace:* : 1.0.0
+├─ old@^1.0.0 : 1.0.0 [DEPRECATED]
+├─ [DEV DEPENDENCIES]
+└─ minor@~1.2.0 : 1.2.0
+"
+        );
+
+        // npm's cache only holds the scoped packument
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/registry")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let registry = Registry::open("tests/registry/_cacache").unwrap();
+        let deprecated = find_deprecated(&graph, &registry).unwrap();
+        assert_eq!(deprecated.len(), 1);
+        assert_eq!(deprecated[0].package.to_string(), "@scope/gone@1.0.0");
+    }
+}
@@ -1,13 +1,17 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
     fmt, io,
     rc::{Rc, Weak},
 };
 
+use clap::ValueEnum;
+use globset::GlobSet;
+
 use crate::{
     extended_version_req::ExtendedVersionReq,
     package::{Dependency, Package, PackageEntry, PackageKey},
+    version_bump::Bump,
 };
 use color_eyre::eyre::Result;
 use colored::*;
@@ -18,6 +22,8 @@ use tracing::debug;
 #[derive(Debug, Clone)]
 pub struct DiffedDependency {
     pub name: String,
+    /// The dependency's name on the left side, if it was renamed
+    pub renamed_from: Option<String>,
     pub package: DiffedPackageAndVersionReq,
 }
 
@@ -38,9 +44,57 @@ pub enum DiffedPackageAndVersionReq {
     },
 }
 
+impl DiffedDependency {
+    /// The dependency's name, and its previous name if it was renamed
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.renamed_from.as_deref())
+    }
+
+    /// Whether the right side leaves the declared range unmet, by installing
+    /// nothing or a version outside of it, while the left side met it
+    pub fn introduces_mismatch(&self) -> bool {
+        match &self.package {
+            DiffedPackageAndVersionReq::Changed {
+                package,
+                version_req_left,
+                version_req_right,
+            } => {
+                let (left_missing, right_missing) = match package {
+                    ChangedPackageEntry::Missing => (true, true),
+                    ChangedPackageEntry::MismatchedResolution { left, right } => (
+                        matches!(left, PackageEntry::Missing),
+                        matches!(right, PackageEntry::Missing),
+                    ),
+                    _ => (false, false),
+                };
+                let left_unmet =
+                    left_missing || package.satisfies(version_req_left, Side::Left) == Some(false);
+                let right_unmet = right_missing
+                    || package.satisfies(version_req_right, Side::Right) == Some(false);
+                right_unmet && !left_unmet
+            }
+            DiffedPackageAndVersionReq::Added {
+                package,
+                version_req,
+            } => {
+                matches!(package, PackageEntry::Missing)
+                    || package.satisfies(version_req) == Some(false)
+            }
+            DiffedPackageAndVersionReq::Removed { .. } => false,
+        }
+    }
+}
+
 impl fmt::Display for DiffedDependency {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match &self.renamed_from {
+            Some(renamed_from) => format!("({} -> {})", renamed_from, self.name),
+            None => self.name.clone(),
+        };
         let prefix = match &self.package {
+            DiffedPackageAndVersionReq::Changed { .. } if self.renamed_from.is_some() => {
+                "[RENAMED] ".yellow().to_string()
+            }
             DiffedPackageAndVersionReq::Changed { .. } => "".to_string(),
             DiffedPackageAndVersionReq::Added { .. } => "[ADDED] ".green().to_string(),
             DiffedPackageAndVersionReq::Removed { .. } => "[REMOVED] ".red().to_string(),
@@ -68,18 +122,26 @@ impl fmt::Display for DiffedDependency {
                 version_req_left,
                 version_req_right,
             } => {
-                let left = match package.satisfies(&version_req_left, Side::Left) {
+                let left = match package.satisfies(version_req_left, Side::Left) {
                     Some(true) => package.version_str(Side::Left).green(),
                     Some(false) => package.version_str(Side::Left).red(),
                     None => package.version_str(Side::Left).into(),
                 };
-                let right = match package.satisfies(&version_req_right, Side::Right) {
+                let right = match package.satisfies(version_req_right, Side::Right) {
                     Some(true) => package.version_str(Side::Right).green(),
                     Some(false) => package.version_str(Side::Right).red(),
                     None => package.version_str(Side::Right).into(),
                 };
-                if left != right {
-                    format!("{} -> {}", left, right)
+                // Compare the plain strings, since only one side may be colored
+                if package.version_str(Side::Left) != package.version_str(Side::Right) {
+                    let bump = match package {
+                        ChangedPackageEntry::Resolved(key) => key.bump(),
+                        _ => None,
+                    };
+                    match bump {
+                        Some(bump) => format!("{} -> {} {}", left, right, bump.badge()),
+                        None => format!("{} -> {}", left, right),
+                    }
                 } else {
                     left.to_string()
                 }
@@ -91,7 +153,7 @@ impl fmt::Display for DiffedDependency {
             | DiffedPackageAndVersionReq::Removed {
                 version_req,
                 package,
-            } => match package.satisfies(&version_req) {
+            } => match package.satisfies(version_req) {
                 Some(true) => package.version_str().green().to_string(),
                 Some(false) => package.version_str().red().to_string(),
                 None => package.version_str(),
@@ -102,7 +164,7 @@ impl fmt::Display for DiffedDependency {
             f,
             "{}{}{}{} {} {}",
             prefix,
-            self.name,
+            name,
             "@".bright_black(),
             version_req_str.bright_blue(),
             ":".bright_black(),
@@ -116,7 +178,11 @@ pub enum ChangedPackageEntry {
     Resolved(ChangedPackageKey),
     Missing,
     Truncated,
-    MismatchedResolution,
+    /// One side resolved a package and the other didn't, e.g. `Missing` vs `Resolved`
+    MismatchedResolution {
+        left: PackageEntry,
+        right: PackageEntry,
+    },
 }
 
 enum Side {
@@ -130,7 +196,27 @@ impl ChangedPackageEntry {
             Self::Resolved(package) => package.version_str(side),
             Self::Missing => "[MISSING]".red().to_string(),
             Self::Truncated => "[TRUNCATED]".yellow().to_string(),
-            Self::MismatchedResolution => "[MISMATCHED RESOLUTION]".yellow().to_string(),
+            Self::MismatchedResolution { left, right } => match side {
+                Side::Left => left.version_str(),
+                Side::Right => right.version_str(),
+            },
+        }
+    }
+
+    /// Classifies a missing dependency being installed or an installed one
+    /// going missing. Truncated entries count as installed.
+    pub fn kind(&self) -> Option<ChangeKind> {
+        match self {
+            Self::Resolved(key) => key.bump().map(ChangeKind::from),
+            Self::MismatchedResolution {
+                right: PackageEntry::Missing,
+                ..
+            } => Some(ChangeKind::BecameMissing),
+            Self::MismatchedResolution {
+                left: PackageEntry::Missing,
+                ..
+            } => Some(ChangeKind::BecameResolved),
+            _ => None,
         }
     }
 
@@ -139,10 +225,13 @@ impl ChangedPackageEntry {
             Self::Resolved(package) => package
                 .version(side)
                 .as_ref()
-                .and_then(|version| version_req.matches(&version)),
+                .and_then(|version| version_req.matches(version)),
             Self::Missing => None,
             Self::Truncated => None,
-            Self::MismatchedResolution => None,
+            Self::MismatchedResolution { left, right } => match side {
+                Side::Left => left.satisfies(version_req),
+                Side::Right => right.satisfies(version_req),
+            },
         }
     }
 }
@@ -154,6 +243,11 @@ pub struct ChangedPackageKey {
 }
 
 impl ChangedPackageKey {
+    /// Classifies the version change, if both sides have a version
+    pub fn bump(&self) -> Option<Bump> {
+        Bump::between(self.left.version.as_ref()?, self.right.version.as_ref()?)
+    }
+
     fn version(&self, side: Side) -> &Option<Version> {
         match side {
             Side::Left => &self.left.version,
@@ -171,13 +265,13 @@ impl ChangedPackageKey {
 
 impl fmt::Display for ChangedPackageKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = if &self.left.name == &self.right.name {
+        let name = if self.left.name == self.right.name {
             self.left.name.clone()
         } else {
             format!("({} -> {})", self.left.name, self.right.name)
         };
 
-        let version_str = if &self.left.version == &self.right.version {
+        let version_str = if self.left.version == self.right.version {
             self.version_str(Side::Left)
         } else {
             format!(
@@ -231,12 +325,16 @@ impl DiffedPackage {
     }
 
     pub fn print_tree(&self, config: &PrintConfig) -> io::Result<()> {
+        self.write_tree(io::stdout(), config)
+    }
+
+    pub fn write_tree<W: io::Write>(&self, f: W, config: &PrintConfig) -> io::Result<()> {
         debug!("Printing diff tree for {}", self);
         self.differ
             .upgrade()
             .expect("Differ is missing")
             .refresh_visited();
-        ptree::print_tree_with(self, config)?;
+        ptree::write_tree_with(self, f, config)?;
         Ok(())
     }
 }
@@ -258,29 +356,317 @@ impl fmt::Display for DiffedPackage {
                 self.version_str(Side::Right)
             )
         };
+        let bump_str = match self.version_left.as_ref().zip(self.version_right.as_ref()) {
+            Some((left, right)) => Bump::between(left, right)
+                .map(|bump| format!(" {}", bump.badge()))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
 
         write!(
             f,
-            "{}{}{}{}",
+            "{}{}{}{}{}",
             self.name,
             "@".bright_black(),
             version_str.blue(),
+            bump_str,
             deduped_str
         )
     }
 }
 
+/// The kinds of change `--only` can select
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChangeKind {
+    Major,
+    Minor,
+    Patch,
+    Prerelease,
+    Downgrade,
+    // Installed on the left side and missing on the right
+    BecameMissing,
+    // Missing on the left side and installed on the right
+    BecameResolved,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Prerelease => "prerelease",
+            Self::Downgrade => "downgrade",
+            Self::BecameMissing => "became missing",
+            Self::BecameResolved => "became resolved",
+        }
+    }
+}
+
+impl From<Bump> for ChangeKind {
+    fn from(bump: Bump) -> Self {
+        match bump {
+            Bump::Major => Self::Major,
+            Bump::Minor => Self::Minor,
+            Bump::Patch => Self::Patch,
+            Bump::Prerelease => Self::Prerelease,
+            Bump::Downgrade => Self::Downgrade,
+        }
+    }
+}
+
+/// Limits which changes are displayed in the diff tree
+#[derive(Debug, Clone, Default)]
+pub struct DiffFilter {
+    /// Only show changes of these kinds (all changes if empty)
+    pub only: Vec<ChangeKind>,
+    /// Only show changes to dependencies whose names match
+    pub include: Option<GlobSet>,
+    /// Hide dependencies whose names match, along with their subtrees
+    pub exclude: Option<GlobSet>,
+    /// Hide the root's dev dependencies
+    pub prod: bool,
+    /// Only show the root's dev dependencies
+    pub dev: bool,
+    /// Hide dependencies deeper than this below the root
+    pub max_depth: Option<usize>,
+}
+
+impl DiffFilter {
+    pub fn matches(&self, kind: Option<ChangeKind>) -> bool {
+        self.only.is_empty() || kind.is_some_and(|kind| self.only.contains(&kind))
+    }
+
+    pub fn matches_bump(&self, bump: Option<Bump>) -> bool {
+        self.matches(bump.map(ChangeKind::from))
+    }
+
+    pub fn includes_name(&self, name: &str) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|globs| globs.is_match(name))
+    }
+
+    pub fn excludes_name(&self, name: &str) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|globs| globs.is_match(name))
+    }
+
+    /// Renamed dependencies match by either name
+    pub(crate) fn includes(&self, dependency: &DiffedDependency) -> bool {
+        dependency.names().any(|name| self.includes_name(name))
+    }
+
+    pub(crate) fn excludes(&self, dependency: &DiffedDependency) -> bool {
+        dependency.names().any(|name| self.excludes_name(name))
+    }
+
+    /// Whether the production or dev dependencies of a package `depth` levels
+    /// below the root are shown. Only the root's dependencies are split.
+    pub fn shows_dependencies(&self, depth: usize, dev: bool) -> bool {
+        depth > 0 || if dev { !self.prod } else { !self.dev }
+    }
+}
+
+/// A package whose installed version differs between the two sides. A
+/// missing version means the package was added or removed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionChange {
+    pub name: String,
+    pub left: Option<Version>,
+    pub right: Option<Version>,
+}
+
+impl VersionChange {
+    pub fn bump(&self) -> Option<Bump> {
+        Bump::between(self.left.as_ref()?, self.right.as_ref()?)
+    }
+}
+
+impl fmt::Display for VersionChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = |version: &Option<Version>| match version {
+            Some(version) => version.to_string(),
+            None => "-".bright_black().to_string(),
+        };
+        write!(
+            f,
+            "{}{} {} -> {}",
+            self.name,
+            ":".bright_black(),
+            version(&self.left),
+            version(&self.right)
+        )?;
+        match (&self.left, &self.right, self.bump()) {
+            (_, _, Some(bump)) => write!(f, " {}", bump.badge()),
+            (None, Some(_), _) => write!(f, " {}", "[ADDED]".green()),
+            (Some(_), None, _) => write!(f, " {}", "[REMOVED]".red()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Totals over the changes the diff filter keeps
+#[derive(Debug, Clone, Default)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub renamed: usize,
+    pub upgraded: usize,
+    pub downgraded: usize,
+    pub became_missing: usize,
+    pub became_resolved: usize,
+    /// Declared ranges that changed while the installed version stayed the same
+    pub requirement_only: usize,
+    /// Deduplicated and sorted by name
+    pub changes: Vec<VersionChange>,
+}
+
+/// Collects the distinct changes of dependencies to count them by kind.
+/// Copies installed in different places differ only in their node_modules
+/// ids, which never match across the two sides, so packages are compared by
+/// name and version.
+#[derive(Debug, Default)]
+pub(crate) struct SummaryCollector {
+    changes: BTreeSet<VersionChange>,
+    added: HashSet<(String, Option<Version>)>,
+    removed: HashSet<(String, Option<Version>)>,
+    requirement_only: BTreeSet<(String, String, String)>,
+    renamed: BTreeSet<(String, String)>,
+    became_missing: BTreeSet<(String, String)>,
+    became_resolved: BTreeSet<(String, String)>,
+}
+
+impl SummaryCollector {
+    pub(crate) fn add(&mut self, dependency: &DiffedDependency) {
+        if let DiffedPackageAndVersionReq::Changed {
+            package: ChangedPackageEntry::Resolved(key),
+            ..
+        } = &dependency.package
+            && key.left.name == key.right.name
+            && key.left.version != key.right.version
+        {
+            self.changes.insert(VersionChange {
+                name: key.left.name.clone(),
+                left: key.left.version.clone(),
+                right: key.right.version.clone(),
+            });
+        }
+
+        if let Some(renamed_from) = &dependency.renamed_from {
+            self.renamed
+                .insert((renamed_from.clone(), dependency.name.clone()));
+            return;
+        }
+        match &dependency.package {
+            DiffedPackageAndVersionReq::Added {
+                package: PackageEntry::Resolved(key),
+                ..
+            } => {
+                self.added.insert((key.name.clone(), key.version.clone()));
+            }
+            DiffedPackageAndVersionReq::Removed {
+                package: PackageEntry::Resolved(key),
+                ..
+            } => {
+                self.removed.insert((key.name.clone(), key.version.clone()));
+            }
+            DiffedPackageAndVersionReq::Changed {
+                package: ChangedPackageEntry::Resolved(key),
+                version_req_left,
+                version_req_right,
+            } if key.left.version == key.right.version && version_req_left != version_req_right => {
+                self.requirement_only.insert((
+                    dependency.name.clone(),
+                    version_req_left.to_string(),
+                    version_req_right.to_string(),
+                ));
+            }
+            DiffedPackageAndVersionReq::Changed {
+                package: package @ ChangedPackageEntry::MismatchedResolution { left, right },
+                ..
+            } => match package.kind() {
+                Some(ChangeKind::BecameMissing) => {
+                    self.became_missing
+                        .insert((dependency.name.clone(), left.to_string()));
+                }
+                Some(ChangeKind::BecameResolved) => {
+                    self.became_resolved
+                        .insert((dependency.name.clone(), right.to_string()));
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Counts the version changes by kind, leaving out added and removed packages
+    pub(crate) fn bump_counts(&self) -> HashMap<Bump, usize> {
+        let mut counts = HashMap::new();
+        for bump in self.changes.iter().filter_map(|change| change.bump()) {
+            *counts.entry(bump).or_default() += 1;
+        }
+        counts
+    }
+
+    pub(crate) fn finish(mut self) -> DiffSummary {
+        // A package added under one dependent and removed under another is
+        // still installed on both sides
+        let moved = self
+            .added
+            .intersection(&self.removed)
+            .cloned()
+            .collect::<Vec<_>>();
+        for package in &moved {
+            self.added.remove(package);
+            self.removed.remove(package);
+        }
+        self.changes
+            .extend(self.added.iter().map(|(name, version)| VersionChange {
+                name: name.clone(),
+                left: None,
+                right: version.clone(),
+            }));
+        self.changes
+            .extend(self.removed.iter().map(|(name, version)| VersionChange {
+                name: name.clone(),
+                left: version.clone(),
+                right: None,
+            }));
+
+        let bumps = self
+            .changes
+            .iter()
+            .filter_map(|c| c.bump())
+            .collect::<Vec<_>>();
+        DiffSummary {
+            added: self.added.len(),
+            removed: self.removed.len(),
+            renamed: self.renamed.len(),
+            upgraded: bumps.iter().filter(|b| **b != Bump::Downgrade).count(),
+            downgraded: bumps.iter().filter(|b| **b == Bump::Downgrade).count(),
+            became_missing: self.became_missing.len(),
+            became_resolved: self.became_resolved.len(),
+            requirement_only: self.requirement_only.len(),
+            changes: self.changes.into_iter().collect(),
+        }
+    }
+}
+
 pub struct Differ {
     diffed_packages: RefCell<HashMap<ChangedPackageKey, Option<Rc<DiffedPackage>>>>,
     diff_queue: RefCell<Vec<ChangedPackageKey>>,
     left: Rc<Package>,
     right: Rc<Package>,
+    pub(crate) filter: DiffFilter,
 }
 
 impl Differ {
     pub fn diff(
         left: Rc<Package>,
         right: Rc<Package>,
+        filter: DiffFilter,
     ) -> Result<(Rc<Self>, Option<Rc<DiffedPackage>>)> {
         debug!("Diffing packages {} and {}", left.name, right.name);
         let self_ = Rc::new(Self {
@@ -288,6 +674,7 @@ impl Differ {
             diff_queue: RefCell::new(Vec::new()),
             left,
             right,
+            filter,
         });
 
         let diffed_package = self_
@@ -331,7 +718,7 @@ impl Differ {
             // keep the recursion going even though the data structure isn't recursive
             self.diff_packages(&left_pkg, &right_pkg);
 
-            if !self.diffed_packages.borrow().get(&key).is_some() {
+            if self.diffed_packages.borrow().get(&key).is_none() {
                 panic!("Diffed package with key {} is missing", key);
             }
         }
@@ -350,7 +737,7 @@ impl Differ {
         };
 
         if let Some(diffed_package) = self.diffed_packages.borrow().get(&key) {
-            return diffed_package.as_ref().map(|rc| Rc::downgrade(&rc));
+            return diffed_package.as_ref().map(Rc::downgrade);
         }
 
         let left = left.clone();
@@ -384,14 +771,14 @@ impl Differ {
 
         self.diffed_packages
             .borrow_mut()
-            .insert(key.clone(), diffed_package.map(|dp| Rc::new(dp)));
+            .insert(key.clone(), diffed_package.map(Rc::new));
 
         return self
             .diffed_packages
             .borrow()
             .get(&key)
             .and_then(|dp| dp.as_ref())
-            .map(|rc| Rc::downgrade(rc));
+            .map(Rc::downgrade);
     }
 
     fn diff_dependencies(
@@ -400,6 +787,7 @@ impl Differ {
         mut right: HashMap<String, Dependency>,
     ) -> HashMap<String, DiffedDependency> {
         let mut dependencies = HashMap::new();
+        let mut removed = Vec::new();
 
         for (name, left_dep) in left {
             if let Some(right_dep) = right.remove(&name) {
@@ -407,24 +795,48 @@ impl Differ {
                     dependencies.insert(name, diffed_dependency);
                 }
             } else {
-                dependencies.insert(
-                    name.clone(),
-                    DiffedDependency {
-                        name,
-                        package: DiffedPackageAndVersionReq::Removed {
-                            package: left_dep.package,
-                            version_req: left_dep.version_req,
-                        },
-                    },
-                );
+                removed.push(left_dep);
             }
         }
 
+        // Sorted so that renames are paired the same way on every run
+        removed.sort_by(|a, b| a.name.cmp(&b.name));
+        for left_dep in removed {
+            let mut renamed_to = right
+                .values()
+                .filter(|right_dep| self.is_rename(&left_dep, right_dep))
+                .map(|right_dep| right_dep.name.clone())
+                .collect::<Vec<_>>();
+            renamed_to.sort();
+            if let Some(right_dep) = renamed_to.first().and_then(|name| right.remove(name))
+                && let Some(mut diffed_dependency) =
+                    self.diff_dependency(left_dep.clone(), right_dep.clone())
+            {
+                diffed_dependency.name = right_dep.name.clone();
+                diffed_dependency.renamed_from = Some(left_dep.name);
+                dependencies.insert(right_dep.name, diffed_dependency);
+                continue;
+            }
+
+            dependencies.insert(
+                left_dep.name.clone(),
+                DiffedDependency {
+                    name: left_dep.name,
+                    renamed_from: None,
+                    package: DiffedPackageAndVersionReq::Removed {
+                        package: left_dep.package,
+                        version_req: left_dep.version_req,
+                    },
+                },
+            );
+        }
+
         for (name, right_dep) in right {
             dependencies.insert(
                 name.clone(),
                 DiffedDependency {
                     name,
+                    renamed_from: None,
                     package: DiffedPackageAndVersionReq::Added {
                         package: right_dep.package,
                         version_req: right_dep.version_req,
@@ -433,7 +845,38 @@ impl Differ {
             );
         }
 
-        return dependencies;
+        dependencies
+    }
+
+    /// Whether a removed and an added dependency install the same package under
+    /// different names, e.g. through an `npm:` alias or a move into a scope
+    /// (`foo` to `@org/foo`). The versions must match, as well as either the
+    /// package.json names or the repositories and unscoped names.
+    fn is_rename(&self, left: &Dependency, right: &Dependency) -> bool {
+        let (PackageEntry::Resolved(left_key), PackageEntry::Resolved(right_key)) =
+            (&left.package, &right.package)
+        else {
+            return false;
+        };
+        if left_key.version.is_none() || left_key.version != right_key.version {
+            return false;
+        }
+        if left_key.name == right_key.name {
+            return true;
+        }
+
+        let unscoped = |name: &str| name.rsplit('/').next().unwrap_or(name).to_string();
+        if unscoped(&left_key.name) != unscoped(&right_key.name) {
+            return false;
+        }
+        let repository = |package: &Rc<Package>, key: &PackageKey| {
+            package
+                .resolver()
+                .and_then(|resolver| resolver.get_package(key))
+                .and_then(|package| package.data.repository.clone())
+        };
+        repository(&self.left, left_key)
+            .is_some_and(|left_repo| repository(&self.right, right_key) == Some(left_repo))
     }
 
     fn diff_dependency(
@@ -449,6 +892,7 @@ impl Differ {
 
         Some(DiffedDependency {
             name,
+            renamed_from: None,
             package: DiffedPackageAndVersionReq::Changed {
                 version_req_left: left.version_req,
                 version_req_right: right.version_req,
@@ -472,7 +916,24 @@ impl Differ {
             (PackageEntry::Truncated, PackageEntry::Truncated) => {
                 Some(ChangedPackageEntry::Truncated)
             }
-            _ => Some(ChangedPackageEntry::MismatchedResolution),
+            (left, right) => Some(ChangedPackageEntry::MismatchedResolution { left, right }),
+        }
+    }
+
+    /// Every package pair that differs, keyed by the left and right package
+    pub fn diffed_packages(&self) -> Vec<(ChangedPackageKey, Rc<DiffedPackage>)> {
+        self.diffed_packages
+            .borrow()
+            .iter()
+            .filter_map(|(key, dp)| Some((key.clone(), dp.clone()?)))
+            .collect()
+    }
+
+    /// The key of the two packages the diff started from
+    pub fn root_key(&self) -> ChangedPackageKey {
+        ChangedPackageKey {
+            left: PackageKey::from(self.left.as_ref()),
+            right: PackageKey::from(self.right.as_ref()),
         }
     }
 
@@ -481,7 +942,76 @@ impl Differ {
             .borrow()
             .get(key)
             .and_then(|dp| dp.as_ref())
-            .map(|rc| rc.clone())
+            .cloned()
+    }
+
+    /// Summarizes the changes the diff filter keeps
+    pub fn summary(&self) -> DiffSummary {
+        let mut collector = SummaryCollector::default();
+        self.walk_filtered(|dependency| collector.add(dependency));
+        collector.finish()
+    }
+
+    /// Counts the distinct version changes the diff filter keeps by kind,
+    /// across several diffs (e.g. one per workspace member). A change counts
+    /// once however many installed copies or diffs it appears in.
+    pub fn bump_counts(differs: &[Rc<Differ>]) -> HashMap<Bump, usize> {
+        let mut collector = SummaryCollector::default();
+        for differ in differs {
+            differ.walk_filtered(|dependency| collector.add(dependency));
+        }
+        collector.bump_counts()
+    }
+
+    /// Calls `visit` with every dependency the diff filter keeps, breadth
+    /// first from the root so that each package is walked at its shallowest
+    /// depth. The root's prod/dev split, `--exclude`, `--include` and
+    /// `--max-depth` apply; dependencies that aren't included are still walked
+    /// through. `--only` is left to the caller.
+    pub(crate) fn walk_filtered(&self, mut visit: impl FnMut(&DiffedDependency)) {
+        let root_key = self.root_key();
+        let Some(root) = self.get_package(&root_key) else {
+            return;
+        };
+
+        let filter = &self.filter;
+        let mut seen = HashSet::from([root_key]);
+        // The root's dependencies are at depth 1
+        let mut queue = VecDeque::from([(root, 1)]);
+        while let Some((package, depth)) = queue.pop_front() {
+            if filter.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                continue;
+            }
+
+            let dependencies = filter
+                .shows_dependencies(depth - 1, false)
+                .then_some(&package.dependencies)
+                .into_iter()
+                .chain(
+                    filter
+                        .shows_dependencies(depth - 1, true)
+                        .then_some(&package.dev_dependencies),
+                )
+                .flat_map(|dependencies| dependencies.values());
+            for dependency in dependencies {
+                if filter.excludes(dependency) {
+                    continue;
+                }
+                if filter.includes(dependency) {
+                    visit(dependency);
+                }
+
+                if let DiffedPackageAndVersionReq::Changed {
+                    package: ChangedPackageEntry::Resolved(key),
+                    ..
+                } = &dependency.package
+                    && seen.insert(key.clone())
+                    && let Some(child) = self.get_package(key)
+                {
+                    queue.push_back((child, depth + 1));
+                }
+            }
+        }
     }
 
     fn refresh_visited(&self) {
@@ -490,3 +1020,352 @@ impl Differ {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    /// Diffs the workspace roots of tests/diff/left and tests/diff/right,
+    /// followed by the members both sides have (app and lib)
+    pub(crate) fn diff_fixture(filter: DiffFilter) -> Vec<Rc<Differ>> {
+        let mut resolver = Resolver::new(usize::MAX);
+        let left =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/diff/left")).unwrap();
+        let right =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/diff/right")).unwrap();
+
+        let mut differs = vec![
+            Differ::diff(left[0].clone(), right[0].clone(), filter.clone())
+                .unwrap()
+                .0,
+        ];
+        for member in crate::paired_members(&left, &right) {
+            if let (Some(left), Some(right)) = member {
+                differs.push(
+                    Differ::diff(left.clone(), right.clone(), filter.clone())
+                        .unwrap()
+                        .0,
+                );
+            }
+        }
+        differs
+    }
+
+    /// The diff tree of a differ, without colors
+    pub(crate) fn render_tree(differ: &Differ) -> String {
+        colored::control::set_override(false);
+        let mut out = Vec::new();
+        differ
+            .get_package(&differ.root_key())
+            .unwrap()
+            .write_tree(&mut out, &PrintConfig::default())
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_bump_counts() {
+        let differs = diff_fixture(DiffFilter::default());
+
+        // app@1.0.0 -> 2.0.0 is reached from the root and diffed as a member,
+        // but counts once
+        let counts = Differ::bump_counts(&differs);
+        assert_eq!(counts[&Bump::Major], 2);
+        assert_eq!(counts[&Bump::Minor], 2);
+        assert_eq!(counts[&Bump::Patch], 2);
+        assert_eq!(counts[&Bump::Prerelease], 1);
+        assert_eq!(counts[&Bump::Downgrade], 1);
+    }
+
+    #[test]
+    fn test_summary() {
+        let differs = diff_fixture(DiffFilter::default());
+
+        // x@1.0.0 moves from app to lib, so the root's diff removes it from one
+        // and adds it to the other while it stays installed
+        let summary = differs[0].summary();
+        assert_eq!(summary.added, 0);
+        assert_eq!(summary.removed, 0);
+        assert_eq!(summary.renamed, 1);
+        assert_eq!(summary.upgraded, 7);
+        assert_eq!(summary.downgraded, 1);
+        assert_eq!(summary.became_missing, 1);
+        assert_eq!(summary.became_resolved, 1);
+        assert_eq!(summary.requirement_only, 0);
+        let names = summary
+            .changes
+            .iter()
+            .map(|change| change.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "app", "deep", "down", "major", "minor", "patch", "pre", "tool"
+            ]
+        );
+
+        // Within the members it really is removed from one and added to the other
+        let app = differs[1].summary();
+        assert_eq!((app.added, app.removed), (0, 1));
+        assert!(
+            app.changes
+                .iter()
+                .any(|change| change.name == "x" && change.right.is_none())
+        );
+        let lib = differs[2].summary();
+        assert_eq!((lib.added, lib.removed), (1, 0));
+    }
+
+    #[test]
+    fn test_renamed_alias() {
+        let differs = diff_fixture(DiffFilter::default());
+        let tree = render_tree(&differs[0]);
+        assert!(
+            tree.contains("├─ [RENAMED] (bar -> foo)@(^1.0.0 -> npm:bar@^1.0.0) : 1.0.0\n"),
+            "{tree}"
+        );
+        assert_eq!(differs[0].summary().renamed, 1);
+    }
+
+    #[test]
+    fn test_mismatched_resolution() {
+        let differs = diff_fixture(DiffFilter::default());
+        let root = differs[0].get_package(&differs[0].root_key()).unwrap();
+        let kind = |name: &str| match &root.dependencies[name].package {
+            DiffedPackageAndVersionReq::Changed { package, .. } => package.kind(),
+            _ => None,
+        };
+        assert_eq!(kind("gone"), Some(ChangeKind::BecameMissing));
+        assert_eq!(kind("late"), Some(ChangeKind::BecameResolved));
+        assert!(root.dependencies["gone"].introduces_mismatch());
+        assert!(!root.dependencies["late"].introduces_mismatch());
+
+        let tree = render_tree(&differs[0]);
+        assert!(
+            tree.contains("├─ gone@^1.0.0 : 1.0.0 -> [MISSING]\n"),
+            "{tree}"
+        );
+        assert!(
+            tree.contains("├─ late@^1.0.0 : [MISSING] -> 1.0.0\n"),
+            "{tree}"
+        );
+    }
+
+    #[test]
+    fn test_only_resolution_filters() {
+        let only = |kind| DiffFilter {
+            only: vec![kind],
+            ..Default::default()
+        };
+        assert_eq!(
+            render_tree(&diff_fixture(only(ChangeKind::BecameMissing))[0]),
+            "\
+diff-fixture@1.0.0
+└─ gone@^1.0.0 : 1.0.0 -> [MISSING]
+"
+        );
+        assert_eq!(
+            render_tree(&diff_fixture(only(ChangeKind::BecameResolved))[0]),
+            "\
+diff-fixture@1.0.0
+└─ late@^1.0.0 : [MISSING] -> 1.0.0
+"
+        );
+    }
+
+    #[test]
+    fn test_name_filters() {
+        let globs = |patterns: &[&str]| {
+            let patterns = patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            Some(crate::source_scan::build_globset(&patterns).unwrap())
+        };
+
+        // major is kept as the path to deep
+        let include = DiffFilter {
+            include: globs(&["deep"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_tree(&diff_fixture(include)[0]),
+            "\
+diff-fixture@1.0.0
+└─ major@(^1.0.0 -> ^2.0.0) : 1.0.0 -> 2.0.0 [MAJOR]
+   └─ deep@^1.0.0 : 1.0.0 -> 1.0.1 [PATCH]
+"
+        );
+
+        // Excluding major hides deep too, and the renamed bar matches by its old name
+        let exclude = DiffFilter {
+            exclude: globs(&["major", "[a-l]*", "pre", "patch", "minor"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            render_tree(&diff_fixture(exclude)[0]),
+            "\
+diff-fixture@1.0.0
+├─ [DEV DEPENDENCIES]
+└─ tool@^1.0.0 : 1.0.0 -> 1.1.0 [MINOR]
+"
+        );
+    }
+
+    #[test]
+    fn test_dependency_type_filters() {
+        let prod = DiffFilter {
+            prod: true,
+            ..Default::default()
+        };
+        let tree = render_tree(&diff_fixture(prod)[0]);
+        assert!(!tree.contains("DEV DEPENDENCIES"), "{tree}");
+        assert!(!tree.contains("tool"), "{tree}");
+        assert!(tree.contains("│  └─ deep@^1.0.0"), "{tree}");
+
+        let dev = DiffFilter {
+            dev: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            render_tree(&diff_fixture(dev)[0]),
+            "\
+diff-fixture@1.0.0
+├─ [DEV DEPENDENCIES]
+└─ tool@^1.0.0 : 1.0.0 -> 1.1.0 [MINOR]
+"
+        );
+    }
+
+    #[test]
+    fn test_max_depth_filter() {
+        let filter = DiffFilter {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let tree = render_tree(&diff_fixture(filter)[0]);
+        assert!(tree.contains("├─ major@(^1.0.0 -> ^2.0.0) : 1.0.0 -> 2.0.0 [MAJOR]\n"));
+        assert!(!tree.contains("deep"), "{tree}");
+        assert!(!tree.contains("x@"), "{tree}");
+        // lib only changed below the root's dependencies
+        assert!(!tree.contains("lib@"), "{tree}");
+    }
+
+    #[test]
+    fn test_max_depth_keeps_shallower_copies() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let left = resolver
+            .resolve(Path::new("tests/diff-depth/left"))
+            .unwrap();
+        let right = resolver
+            .resolve(Path::new("tests/diff-depth/right"))
+            .unwrap();
+        let filter = DiffFilter {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let differ = Differ::diff(left, right, filter).unwrap().0;
+
+        // z is first reached below a, where its children are cut, so the copy
+        // directly below the root still shows the major change to w
+        assert_eq!(
+            render_tree(&differ),
+            "\
+depth-fixture@1.0.0
+├─ a@^1.0.0 : 1.0.0
+│  └─ z@^1.0.0 : 1.0.0 -> 1.1.0 [MINOR]
+└─ z@^1.0.0 : 1.0.0 -> 1.1.0 [MINOR]
+   └─ w@(^1.0.0 -> ^2.0.0) : 1.0.0 -> 2.0.0 [MAJOR]
+"
+        );
+        let names = differ
+            .summary()
+            .changes
+            .into_iter()
+            .map(|change| change.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["w", "z"]);
+    }
+
+    #[test]
+    fn test_summary_filters() {
+        let names = |filter: DiffFilter| {
+            diff_fixture(filter)[0]
+                .summary()
+                .changes
+                .into_iter()
+                .map(|change| change.name)
+                .collect::<Vec<_>>()
+        };
+        let globs = |patterns: &[&str]| {
+            let patterns = patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            Some(crate::source_scan::build_globset(&patterns).unwrap())
+        };
+
+        assert_eq!(
+            names(DiffFilter {
+                dev: true,
+                ..Default::default()
+            }),
+            ["tool"]
+        );
+        assert!(
+            !names(DiffFilter {
+                prod: true,
+                ..Default::default()
+            })
+            .contains(&"tool".to_string())
+        );
+        assert_eq!(
+            names(DiffFilter {
+                include: globs(&["deep"]),
+                ..Default::default()
+            }),
+            ["deep"]
+        );
+        // Excluding major hides deep below it
+        let excluded = names(DiffFilter {
+            exclude: globs(&["major"]),
+            ..Default::default()
+        });
+        assert!(!excluded.contains(&"major".to_string()), "{excluded:?}");
+        assert!(!excluded.contains(&"deep".to_string()), "{excluded:?}");
+        assert!(
+            !names(DiffFilter {
+                max_depth: Some(1),
+                ..Default::default()
+            })
+            .contains(&"deep".to_string())
+        );
+
+        let counts = Differ::bump_counts(&diff_fixture(DiffFilter {
+            dev: true,
+            ..Default::default()
+        }));
+        assert_eq!(counts.values().sum::<usize>(), 1);
+        assert_eq!(counts[&Bump::Minor], 1);
+    }
+
+    #[test]
+    fn test_only_filter() {
+        let filter = DiffFilter {
+            only: vec![ChangeKind::Major, ChangeKind::Downgrade],
+            ..Default::default()
+        };
+        assert!(filter.matches_bump(Some(Bump::Major)));
+        assert!(!filter.matches_bump(Some(Bump::Minor)));
+        assert!(!filter.matches_bump(None));
+
+        // The patch to deep below major is hidden along with the other kinds
+        let differs = diff_fixture(filter);
+        assert_eq!(
+            render_tree(&differs[0]),
+            "\
+diff-fixture@1.0.0
+├─ app@workspace:* : 1.0.0 -> 2.0.0 [MAJOR]
+├─ down@>=1.0.0 : 2.0.0 -> 1.5.0 [DOWNGRADE]
+└─ major@(^1.0.0 -> ^2.0.0) : 1.0.0 -> 2.0.0 [MAJOR]
+"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use color_eyre::eyre::Result;
+use colored::*;
+use serde::Serialize;
+
+use crate::{
+    diff::{ChangedPackageEntry, DiffFilter, DiffedPackageAndVersionReq, Differ},
+    extended_version_req::ExtendedVersionReq,
+    package::{Package, PackageEntry, PackageKey},
+};
+
+/// A dependency edge: a dependency declared by a package of the base tree
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Edge {
+    pub dependent: PackageKey,
+    pub dependency: String,
+    pub dev: bool,
+}
+
+/// What an edge looks like on one side
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum EdgeState {
+    Absent,
+    Present {
+        version_req: String,
+        /// The installed version, or a marker such as `[MISSING]`
+        resolved: String,
+    },
+}
+
+impl fmt::Display for EdgeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Absent => write!(f, "{}", "[ABSENT]".bright_black()),
+            Self::Present {
+                version_req,
+                resolved,
+            } => write!(
+                f,
+                "{}{} {}",
+                version_req.bright_blue(),
+                ":".bright_black(),
+                resolved
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Classification {
+    /// Both sides changed the edge differently
+    Conflicting,
+    OursOnly,
+    TheirsOnly,
+    BothSame,
+}
+
+impl Classification {
+    pub fn label(&self) -> ColoredString {
+        match self {
+            Self::Conflicting => "[CONFLICT]".red().bold(),
+            Self::OursOnly => "[OURS]".green(),
+            Self::TheirsOnly => "[THEIRS]".blue(),
+            Self::BothSame => "[BOTH]".bright_black(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EdgeChange {
+    #[serde(flatten)]
+    pub edge: Edge,
+    pub classification: Classification,
+    pub base: EdgeState,
+    pub ours: EdgeState,
+    pub theirs: EdgeState,
+}
+
+impl fmt::Display for EdgeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dev = if self.edge.dev { " (dev)" } else { "" };
+        write!(
+            f,
+            "{} {} {} {}{}",
+            self.classification.label(),
+            self.edge.dependent,
+            ">".bright_black(),
+            self.edge.dependency,
+            dev.bright_black()
+        )?;
+        write!(f, "\n  {:<7}{}", "base", self.base)?;
+        match self.classification {
+            Classification::OursOnly => write!(f, "\n  {:<7}{}", "ours", self.ours),
+            Classification::TheirsOnly => write!(f, "\n  {:<7}{}", "theirs", self.theirs),
+            _ => write!(
+                f,
+                "\n  {:<7}{}\n  {:<7}{}",
+                "ours", self.ours, "theirs", self.theirs
+            ),
+        }
+    }
+}
+
+/// Diffs both sides against the common base and classifies every changed
+/// edge, conflicts first.
+pub fn diff3(
+    base: &Rc<Package>,
+    ours: &Rc<Package>,
+    theirs: &Rc<Package>,
+) -> Result<Vec<EdgeChange>> {
+    let ours_changes = changed_edges(base, ours)?;
+    let mut theirs_changes = changed_edges(base, theirs)?;
+
+    let mut changes = Vec::new();
+    for (edge, ours_states) in ours_changes {
+        let theirs_states = theirs_changes.remove(&edge).unwrap_or_default();
+        changes.extend(classify(&edge, ours_states, theirs_states));
+    }
+    for (edge, theirs_states) in theirs_changes {
+        changes.extend(classify(&edge, Vec::new(), theirs_states));
+    }
+
+    changes.sort_by_cached_key(|change| {
+        (
+            change.classification,
+            change.edge.dependent.name.clone(),
+            change.edge.dependent.version.clone(),
+            change.edge.dependency.clone(),
+        )
+    });
+    Ok(changes)
+}
+
+/// Matches the changes both sides made to one edge. A base package can be
+/// paired with several packages of a side (one per installed copy), so each
+/// side may change the edge more than once.
+fn classify(
+    edge: &Edge,
+    ours: Vec<(EdgeState, EdgeState)>,
+    mut theirs: Vec<(EdgeState, EdgeState)>,
+) -> Vec<EdgeChange> {
+    let mut changes = Vec::new();
+    let mut unmatched = Vec::new();
+    for (base, ours_state) in ours {
+        match theirs.iter().position(|(_, state)| *state == ours_state) {
+            Some(index) => {
+                let (_, theirs_state) = theirs.remove(index);
+                changes.push(EdgeChange {
+                    edge: edge.clone(),
+                    classification: Classification::BothSame,
+                    base,
+                    ours: ours_state,
+                    theirs: theirs_state,
+                });
+            }
+            None => unmatched.push((base, ours_state)),
+        }
+    }
+
+    let mut theirs = theirs.into_iter();
+    for (base, ours_state) in unmatched {
+        let (classification, theirs_state) = match theirs.next() {
+            Some((_, theirs_state)) => (Classification::Conflicting, theirs_state),
+            None => (Classification::OursOnly, base.clone()),
+        };
+        changes.push(EdgeChange {
+            edge: edge.clone(),
+            classification,
+            base,
+            ours: ours_state,
+            theirs: theirs_state,
+        });
+    }
+    for (base, theirs_state) in theirs {
+        changes.push(EdgeChange {
+            edge: edge.clone(),
+            classification: Classification::TheirsOnly,
+            ours: base.clone(),
+            base,
+            theirs: theirs_state,
+        });
+    }
+    changes
+}
+
+/// Returns the edges that differ between the base and the other side, with
+/// their states on both sides for every pairing of the dependent
+fn changed_edges(
+    base: &Rc<Package>,
+    other: &Rc<Package>,
+) -> Result<HashMap<Edge, Vec<(EdgeState, EdgeState)>>> {
+    let (differ, _) = Differ::diff(base.clone(), other.clone(), DiffFilter::default())?;
+    let mut edges = HashMap::new();
+
+    for (key, diffed_package) in differ.diffed_packages() {
+        let dependencies = diffed_package
+            .dependencies
+            .iter()
+            .map(|d| (d, false))
+            .chain(diffed_package.dev_dependencies.iter().map(|d| (d, true)));
+
+        for ((name, dependency), dev) in dependencies {
+            let states = match &dependency.package {
+                DiffedPackageAndVersionReq::Changed {
+                    package,
+                    version_req_left,
+                    version_req_right,
+                } => {
+                    let (left, right) = match package {
+                        ChangedPackageEntry::Resolved(key) => (
+                            present(version_req_left, key.left.version_str()),
+                            present(version_req_right, key.right.version_str()),
+                        ),
+                        ChangedPackageEntry::Missing => (
+                            present(version_req_left, "[MISSING]".into()),
+                            present(version_req_right, "[MISSING]".into()),
+                        ),
+                        ChangedPackageEntry::Truncated => continue,
+                        ChangedPackageEntry::MismatchedResolution { left, right } => (
+                            present(version_req_left, entry_str(left)),
+                            present(version_req_right, entry_str(right)),
+                        ),
+                    };
+                    if left == right && dependency.renamed_from.is_none() {
+                        continue;
+                    }
+                    (left, right)
+                }
+                DiffedPackageAndVersionReq::Added {
+                    package,
+                    version_req,
+                } => (EdgeState::Absent, present(version_req, entry_str(package))),
+                DiffedPackageAndVersionReq::Removed {
+                    package,
+                    version_req,
+                } => (present(version_req, entry_str(package)), EdgeState::Absent),
+            };
+
+            let changes: &mut Vec<_> = edges
+                .entry(Edge {
+                    dependent: key.left.clone(),
+                    dependency: name.clone(),
+                    dev,
+                })
+                .or_default();
+            if !changes.contains(&states) {
+                changes.push(states);
+            }
+        }
+    }
+
+    Ok(edges)
+}
+
+fn present(version_req: &ExtendedVersionReq, resolved: String) -> EdgeState {
+    EdgeState::Present {
+        version_req: version_req.to_string(),
+        resolved,
+    }
+}
+
+fn entry_str(entry: &PackageEntry) -> String {
+    match entry {
+        PackageEntry::Resolved(key) => key.version_str(),
+        PackageEntry::Missing => "[MISSING]".to_string(),
+        PackageEntry::Truncated => "[TRUNCATED]".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    fn state(resolved: &str) -> EdgeState {
+        EdgeState::Present {
+            version_req: "^1.0.0".into(),
+            resolved: resolved.into(),
+        }
+    }
+
+    #[test]
+    fn test_diff3() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let base = resolver.resolve(Path::new("tests/diff3/base")).unwrap();
+        let ours = resolver.resolve(Path::new("tests/diff3/ours")).unwrap();
+        let theirs = resolver.resolve(Path::new("tests/diff3/theirs")).unwrap();
+
+        let changes = diff3(&base, &ours, &theirs)
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                (
+                    change.classification,
+                    change.edge.dependency,
+                    change.base,
+                    change.ours,
+                    change.theirs,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                (
+                    Classification::Conflicting,
+                    "clash".into(),
+                    state("1.0.0"),
+                    state("1.3.0"),
+                    state("1.4.0")
+                ),
+                (
+                    Classification::OursOnly,
+                    "mine".into(),
+                    state("1.0.0"),
+                    state("1.1.0"),
+                    state("1.0.0")
+                ),
+                (
+                    Classification::TheirsOnly,
+                    "yours".into(),
+                    state("1.0.0"),
+                    state("1.0.0"),
+                    state("1.1.0")
+                ),
+                (
+                    Classification::BothSame,
+                    "both".into(),
+                    state("1.0.0"),
+                    state("1.2.0"),
+                    state("1.2.0")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_keeps_every_pairing() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let base = resolver.resolve(Path::new("tests/diff3/base")).unwrap();
+        let edge = Edge {
+            dependent: PackageKey::from(base.as_ref()),
+            dependency: "clash".into(),
+            dev: false,
+        };
+
+        // Two copies on our side, one of which theirs agrees with
+        let ours = vec![
+            (state("1.0.0"), state("1.2.0")),
+            (state("1.0.0"), state("1.3.0")),
+        ];
+        let theirs = vec![(state("1.0.0"), state("1.3.0"))];
+        let classifications = classify(&edge, ours, theirs)
+            .into_iter()
+            .map(|change| (change.classification, change.ours))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            classifications,
+            [
+                (Classification::BothSame, state("1.3.0")),
+                (Classification::OursOnly, state("1.2.0")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{fmt, fs, path::PathBuf};
+
+use color_eyre::eyre::{Result, eyre};
+use colored::*;
+use semver::Version;
+use serde::Serialize;
+
+use crate::{
+    extended_version_req::ExtendedVersionReq,
+    graph::{DependencyGraph, format_path},
+    package::PackageKey,
+    package_data::PackageJsonData,
+};
+
+/// How many dependency chains are shown per violation
+const MAX_PATHS: usize = 3;
+
+/// An installed package whose `engines.node` range excludes the target version
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineViolation {
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    pub required: String,
+    pub paths: Vec<Vec<PackageKey>>,
+}
+
+impl fmt::Display for EngineViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.package,
+            format!("(requires node {})", self.required).red()
+        )?;
+        for path in &self.paths {
+            write!(f, "\n  {}", format_path(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the target Node version came from
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetNode {
+    pub version: Version,
+    pub source: String,
+}
+
+/// Picks the Node version to check against when none is given: the lowest
+/// version allowed by the package's own `engines.node`, falling back to
+/// `.nvmrc` and `.node-version`.
+pub fn default_target_node(package: &PackageJsonData) -> Result<TargetNode> {
+    if let Some(version) = package
+        .engines
+        .get("node")
+        .and_then(|req| req.min_version())
+    {
+        return Ok(TargetNode {
+            version,
+            source: "engines.node".to_string(),
+        });
+    }
+
+    for file in [".nvmrc", ".node-version"] {
+        let path = package.install_path.join(file);
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let version = parse_node_version(&content)
+            .ok_or_else(|| eyre!("Unable to parse node version in {}", path.display()))?;
+        return Ok(TargetNode {
+            version,
+            source: file.to_string(),
+        });
+    }
+
+    Err(eyre!(
+        "No node version given and none found in engines.node, .nvmrc or .node-version in {}",
+        package.install_path.display()
+    ))
+}
+
+/// Parses versions the way version managers write them, e.g. `v20.11.1` or `20`
+pub fn parse_node_version(version_str: &str) -> Option<Version> {
+    let version_str = version_str.trim();
+    let version_str = version_str.strip_prefix('v').unwrap_or(version_str);
+    let mut parts = version_str.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    Some(Version::new(major, minor, patch))
+}
+
+/// Finds every package in the graph whose `engines.node` excludes the given version
+pub fn check_engines(graph: &DependencyGraph, node: &Version) -> Vec<EngineViolation> {
+    graph
+        .packages()
+        .filter_map(|package| {
+            let required: &ExtendedVersionReq = package.data.engines.get("node")?;
+            if required.matches(node) != Some(false) {
+                return None;
+            }
+
+            let key = PackageKey::from(package.as_ref());
+            Some(EngineViolation {
+                paths: graph.paths_to(&key, MAX_PATHS),
+                package: key,
+                install_path: package.data.install_path.clone(),
+                required: required.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_version() {
+        assert_eq!(
+            parse_node_version("v20.11.1\n"),
+            Some(Version::new(20, 11, 1))
+        );
+        assert_eq!(parse_node_version("18"), Some(Version::new(18, 0, 0)));
+        assert_eq!(parse_node_version("22.3"), Some(Version::new(22, 3, 0)));
+        assert_eq!(parse_node_version("lts/iron"), None);
+    }
+}
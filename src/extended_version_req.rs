@@ -1,4 +1,4 @@
-use semver::{Version, VersionReq};
+use semver::{Op, Version, VersionReq};
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -11,18 +11,35 @@ pub enum ExtendedVersionReq {
 
 impl ExtendedVersionReq {
     pub fn parse(version_str: &str) -> Self {
-        if let Ok(semver_req) = VersionReq::parse(version_str) {
-            return Self::SemVer(semver_req);
-        } else if version_str.starts_with("workspace:") {
-            return Self::Workspace(version_str[10..].to_string());
+        Self::parse_with(version_str, false)
+    }
+
+    /// Parses an `engines` range, which also accepts the npm range syntax the
+    /// semver crate doesn't (see [`normalize_npm_range`]). Dependency ranges
+    /// in that syntax are left unchecked.
+    pub fn parse_engine(version_str: &str) -> Self {
+        Self::parse_with(version_str, true)
+    }
+
+    fn parse_with(version_str: &str, npm_ranges: bool) -> Self {
+        if let Ok(semver_req) = VersionReq::parse(version_str).or_else(|error| {
+            if npm_ranges {
+                VersionReq::parse(&normalize_npm_range(version_str))
+            } else {
+                Err(error)
+            }
+        }) {
+            Self::SemVer(semver_req)
+        } else if let Some(path) = version_str.strip_prefix("workspace:") {
+            Self::Workspace(path.to_string())
         } else if version_str.contains(" || ") {
             let version_reqs = version_str
                 .split(" || ")
-                .map(|version_str| Self::parse(version_str))
+                .map(|version_str| Self::parse_with(version_str, npm_ranges))
                 .collect::<Vec<_>>();
-            return Self::Or(version_reqs);
+            Self::Or(version_reqs)
         } else {
-            return Self::Unchecked(version_str.to_string());
+            Self::Unchecked(version_str.to_string())
         }
     }
 
@@ -38,13 +55,72 @@ impl ExtendedVersionReq {
             _ => None,
         }
     }
+
+    /// Returns the lowest version that satisfies the requirement, ignoring
+    /// pre-releases, e.g. `18.0.0` for `>=18`.
+    pub fn min_version(&self) -> Option<Version> {
+        match self {
+            Self::SemVer(version_req) => {
+                let mut min = Version::new(0, 0, 0);
+                for comparator in &version_req.comparators {
+                    let (major, minor, patch) = (
+                        comparator.major,
+                        comparator.minor.unwrap_or(0),
+                        comparator.patch.unwrap_or(0),
+                    );
+                    let bound = match comparator.op {
+                        Op::Less | Op::LessEq => continue,
+                        Op::Greater => match (comparator.minor, comparator.patch) {
+                            (None, _) => Version::new(major + 1, 0, 0),
+                            (Some(_), None) => Version::new(major, minor + 1, 0),
+                            (Some(_), Some(_)) => Version::new(major, minor, patch + 1),
+                        },
+                        _ => Version::new(major, minor, patch),
+                    };
+                    if bound > min {
+                        min = bound;
+                    }
+                }
+                Some(min)
+            }
+            Self::Or(version_reqs) => version_reqs
+                .iter()
+                .filter_map(|version_req| version_req.min_version())
+                .min(),
+            _ => None,
+        }
+    }
+}
+
+/// Rewrites npm range syntax the semver crate doesn't accept, i.e. hyphen
+/// ranges (`1.2 - 2.3`) and space separated comparators (`>=10 <12`), into
+/// comma separated comparators.
+fn normalize_npm_range(version_str: &str) -> String {
+    if let Some((low, high)) = version_str.split_once(" - ") {
+        return format!(">={}, <={}", low.trim(), high.trim());
+    }
+
+    let mut comparators = Vec::new();
+    let mut pending_op = String::new();
+    for token in version_str.split_whitespace() {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            // An operator separated from its version, e.g. `>= 12`
+            pending_op.push_str(token);
+        } else {
+            comparators.push(format!("{}{}", pending_op, token));
+            pending_op.clear();
+        }
+    }
+    comparators.join(", ")
 }
 
 impl PartialEq for ExtendedVersionReq {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::SemVer(a), Self::SemVer(b)) => a.to_string() == b.to_string(),
-            (Self::Unchecked(a), Self::Unchecked(b)) => a == b,
+            (Self::Unchecked(a), Self::Unchecked(b)) | (Self::Workspace(a), Self::Workspace(b)) => {
+                a == b
+            }
             (Self::Or(a), Self::Or(b)) => a.iter().all(|a| b.iter().any(|b| a.eq(b))),
             _ => false,
         }
@@ -69,3 +145,40 @@ impl fmt::Display for ExtendedVersionReq {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_ranges() {
+        let req = ExtendedVersionReq::parse_engine(">=10 <12");
+        assert_eq!(req.matches(&Version::new(11, 2, 0)), Some(true));
+        assert_eq!(req.matches(&Version::new(12, 0, 0)), Some(false));
+
+        let req = ExtendedVersionReq::parse_engine("1.2 - 2.3");
+        assert_eq!(req.matches(&Version::new(2, 3, 9)), Some(true));
+        assert_eq!(req.matches(&Version::new(2, 4, 0)), Some(false));
+
+        let req = ExtendedVersionReq::parse_engine("^14.18.0 || >= 16");
+        assert_eq!(req.matches(&Version::new(16, 3, 0)), Some(true));
+        assert_eq!(req.matches(&Version::new(14, 17, 0)), Some(false));
+
+        // Dependency ranges are only checked when the semver crate accepts them
+        let req = ExtendedVersionReq::parse(">=10 <12");
+        assert_eq!(req, ExtendedVersionReq::Unchecked(">=10 <12".to_string()));
+        assert_eq!(req.to_string(), ">=10 <12");
+        assert_eq!(req.matches(&Version::new(11, 2, 0)), None);
+    }
+
+    #[test]
+    fn test_min_version() {
+        let min = |s: &str| ExtendedVersionReq::parse_engine(s).min_version();
+        assert_eq!(min(">=18"), Some(Version::new(18, 0, 0)));
+        assert_eq!(min("^20.11"), Some(Version::new(20, 11, 0)));
+        assert_eq!(min(">16.1"), Some(Version::new(16, 2, 0)));
+        assert_eq!(min("^14.18.0 || >=16.0.0"), Some(Version::new(14, 18, 0)));
+        assert_eq!(min("*"), Some(Version::new(0, 0, 0)));
+        assert_eq!(min("latest"), None);
+    }
+}
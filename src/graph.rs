@@ -0,0 +1,161 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use colored::*;
+
+use crate::package::{Package, PackageEntry, PackageKey};
+
+/// A flat view of the packages reachable from a set of root packages, with
+/// the reverse (dependent) edges needed to explain why a package is installed.
+#[derive(Debug)]
+pub struct DependencyGraph {
+    roots: Vec<PackageKey>,
+    // Packages in breadth-first order
+    order: Vec<PackageKey>,
+    packages: HashMap<PackageKey, Rc<Package>>,
+    depths: HashMap<PackageKey, usize>,
+    dependents: HashMap<PackageKey, Vec<PackageKey>>,
+}
+
+impl DependencyGraph {
+    /// Walks the resolved dependencies of the roots. Dev dependencies are only
+    /// followed when `include_dev` is set (they only exist on first-party packages).
+    pub fn from_roots(roots: &[Rc<Package>], include_dev: bool) -> Self {
+        let mut graph = Self {
+            roots: Vec::new(),
+            order: Vec::new(),
+            packages: HashMap::new(),
+            depths: HashMap::new(),
+            dependents: HashMap::new(),
+        };
+
+        let mut queue = VecDeque::new();
+        for root in roots {
+            let key = PackageKey::from(root.as_ref());
+            if graph.packages.contains_key(&key) {
+                continue;
+            }
+            graph.roots.push(key.clone());
+            graph.insert(key, root.clone(), 0, &mut queue);
+        }
+
+        while let Some(key) = queue.pop_front() {
+            let package = graph.packages[&key].clone();
+            let Some(resolver) = package.resolver() else {
+                continue;
+            };
+            let depth = graph.depths[&key];
+
+            let dev_dependencies = include_dev
+                .then_some(&package.dev_dependencies)
+                .into_iter()
+                .flat_map(|deps| deps.values());
+            for dependency in package.dependencies.values().chain(dev_dependencies) {
+                let PackageEntry::Resolved(child_key) = &dependency.package else {
+                    continue;
+                };
+
+                let dependents = graph.dependents.entry(child_key.clone()).or_default();
+                if !dependents.contains(&key) {
+                    dependents.push(key.clone());
+                }
+
+                if !graph.packages.contains_key(child_key)
+                    && let Some(child) = resolver.get_package(child_key)
+                {
+                    graph.insert(child_key.clone(), child, depth + 1, &mut queue);
+                }
+            }
+        }
+
+        graph
+    }
+
+    fn insert(
+        &mut self,
+        key: PackageKey,
+        package: Rc<Package>,
+        depth: usize,
+        queue: &mut VecDeque<PackageKey>,
+    ) {
+        self.order.push(key.clone());
+        self.packages.insert(key.clone(), package);
+        self.depths.insert(key.clone(), depth);
+        queue.push_back(key);
+    }
+
+    /// All reachable packages (including the roots) in breadth-first order
+    pub fn packages(&self) -> impl Iterator<Item = &Rc<Package>> {
+        self.order.iter().map(|key| &self.packages[key])
+    }
+
+    pub fn get(&self, key: &PackageKey) -> Option<&Rc<Package>> {
+        self.packages.get(key)
+    }
+
+    /// The length of the shortest path from a root to the package
+    pub fn depth(&self, key: &PackageKey) -> Option<usize> {
+        self.depths.get(key).copied()
+    }
+
+    /// The packages that depend on the given package
+    pub fn dependents(&self, key: &PackageKey) -> &[PackageKey] {
+        self.dependents.get(key).map_or(&[], |d| d.as_slice())
+    }
+
+    /// Returns up to `limit` dependency chains from a root down to the
+    /// package, shortest first.
+    pub fn paths_to(&self, key: &PackageKey, limit: usize) -> Vec<Vec<PackageKey>> {
+        let mut paths = Vec::new();
+        let mut current = vec![key.clone()];
+        let mut on_path = HashSet::from([key.clone()]);
+        self.collect_paths(&mut current, &mut on_path, &mut paths, limit);
+
+        for path in paths.iter_mut() {
+            path.reverse();
+        }
+        paths.sort_by_key(|path| path.len());
+        paths
+    }
+
+    fn collect_paths(
+        &self,
+        current: &mut Vec<PackageKey>,
+        on_path: &mut HashSet<PackageKey>,
+        paths: &mut Vec<Vec<PackageKey>>,
+        limit: usize,
+    ) {
+        let last = current.last().expect("path is never empty").clone();
+        if self.roots.contains(&last) {
+            paths.push(current.clone());
+            return;
+        }
+
+        // Visit the dependents closest to a root first so the shortest
+        // chains are found before the limit is hit
+        let mut dependents = self.dependents(&last).to_vec();
+        dependents.sort_by_key(|key| self.depth(key));
+        for dependent in dependents {
+            if paths.len() >= limit {
+                return;
+            }
+            if !on_path.insert(dependent.clone()) {
+                continue; // Cycle
+            }
+            current.push(dependent.clone());
+            self.collect_paths(current, on_path, paths, limit);
+            current.pop();
+            on_path.remove(&dependent);
+        }
+    }
+}
+
+/// Formats a dependency chain as `root > dep > ... > package`
+pub fn format_path(path: &[PackageKey]) -> String {
+    path.iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join(&" > ".bright_black().to_string())
+}
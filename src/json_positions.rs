@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+/// How deep keys are recorded, e.g. 2 for `dependencies` and `dependencies/react`
+const MAX_DEPTH: usize = 2;
+
+/// A 1-based line and column in a source file. Columns count UTF-16 code
+/// units, as SARIF does by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where the keys of a JSON document start, so that problems with a
+/// package.json entry can point at its line
+#[derive(Debug, Clone, Default)]
+pub struct SourcePositions {
+    /// Keyed by the `/` separated path of object keys, e.g. `dependencies/react`
+    keys: HashMap<String, Position>,
+}
+
+impl SourcePositions {
+    /// Scans the (already validated) JSON text for object keys. Keys inside
+    /// arrays aren't recorded.
+    pub fn scan(content: &str) -> Self {
+        let mut keys = HashMap::new();
+        // The path of each open container, or None for arrays and anything in them
+        let mut stack: Vec<Option<String>> = Vec::new();
+        let mut last_key: Option<String> = None;
+        let mut expecting_key = false;
+        let mut line = 1;
+        let mut line_start = 0;
+
+        let mut chars = content.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\n' => {
+                    line += 1;
+                    line_start = i + 1;
+                }
+                '{' => {
+                    let path = match stack.last() {
+                        None => Some(String::new()),
+                        Some(Some(_)) => last_key.take(),
+                        Some(None) => None,
+                    };
+                    stack.push(path);
+                    expecting_key = true;
+                }
+                '[' => {
+                    stack.push(None);
+                    expecting_key = false;
+                }
+                '}' | ']' => {
+                    stack.pop();
+                    expecting_key = false;
+                }
+                ',' => expecting_key = matches!(stack.last(), Some(Some(_))),
+                '"' => {
+                    let mut end = i + 1;
+                    let mut escaped = false;
+                    for (j, c) in chars.by_ref() {
+                        end = j + c.len_utf8();
+                        match c {
+                            '\\' if !escaped => escaped = true,
+                            '"' if !escaped => break,
+                            _ => escaped = false,
+                        }
+                    }
+                    if !expecting_key {
+                        continue;
+                    }
+                    expecting_key = false;
+
+                    let Some(Some(parent)) = stack.last() else {
+                        continue;
+                    };
+                    let Ok(key) = serde_json::from_str::<String>(&content[i..end]) else {
+                        continue;
+                    };
+                    let path = if parent.is_empty() {
+                        key
+                    } else {
+                        format!("{}/{}", parent, key)
+                    };
+                    if stack.len() <= MAX_DEPTH {
+                        let column = content[line_start..i].encode_utf16().count() + 1;
+                        keys.insert(path.clone(), Position { line, column });
+                    }
+                    last_key = Some(path);
+                }
+                _ => {}
+            }
+        }
+
+        Self { keys }
+    }
+
+    /// The position of the key at the given path, e.g. `["dependencies", "react"]`
+    pub fn get(&self, path: &[&str]) -> Option<Position> {
+        self.keys.get(&path.join("/")).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let positions = SourcePositions::scan(
+            r#"{
+  "name": "app",
+  "files": [{ "name": "x" }],
+  "dependencies": {
+    "a\"b": "^1.0.0", "react": "^18.0.0"
+  },
+  "engines": { "node": ">=18" }
+}"#,
+        );
+
+        assert_eq!(
+            positions.get(&["name"]),
+            Some(Position { line: 2, column: 3 })
+        );
+        assert_eq!(
+            positions.get(&["dependencies", "a\"b"]),
+            Some(Position { line: 5, column: 5 })
+        );
+        assert_eq!(
+            positions.get(&["dependencies", "react"]),
+            Some(Position {
+                line: 5,
+                column: 23
+            })
+        );
+        assert_eq!(
+            positions.get(&["engines", "node"]),
+            Some(Position {
+                line: 7,
+                column: 16
+            })
+        );
+        assert_eq!(positions.get(&["files", "name"]), None);
+    }
+
+    #[test]
+    fn test_scan_counts_utf16_columns() {
+        // The emoji is two UTF-16 code units, é is one
+        let positions = SourcePositions::scan("{ \"description\": \"é😀\", \"name\": \"app\" }");
+        assert_eq!(
+            positions.get(&["name"]),
+            Some(Position {
+                line: 1,
+                column: 25
+            })
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{graph::DependencyGraph, package::PackageKey, package_data::PackageJsonData};
+
+/// Phrases used to recognize common licenses from their LICENSE file, checked in order
+const LICENSE_TEXTS: &[(&str, &[&str])] = &[
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License Version 2.0"]),
+    (
+        "AGPL-3.0",
+        &["GNU AFFERO GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-3.0",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Unlicense", &["This is free and unencumbered software"]),
+    (
+        "BSD-3-Clause",
+        &["Redistribution and use", "Neither the name"],
+    ),
+    ("BSD-2-Clause", &["Redistribution and use"]),
+    (
+        "ISC",
+        &[
+            "Permission to use, copy, modify, and/or distribute",
+            "provided that the above copyright notice",
+        ],
+    ),
+    (
+        "0BSD",
+        &["Permission to use, copy, modify, and/or distribute"],
+    ),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseSource {
+    PackageJson,
+    LicenseFile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseInfo {
+    /// The SPDX expression, or None if the license couldn't be determined
+    pub expression: Option<String>,
+    pub source: Option<LicenseSource>,
+}
+
+impl fmt::Display for LicenseInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.expression, self.source) {
+            (Some(expression), Some(LicenseSource::LicenseFile)) => {
+                write!(f, "{} {}", expression, "(from license file)".bright_black())
+            }
+            (Some(expression), _) => write!(f, "{}", expression),
+            (None, _) => write!(f, "{}", "[UNKNOWN]".yellow()),
+        }
+    }
+}
+
+/// Determines the license of a package from its package.json, falling back
+/// to recognizing the text of its LICENSE file.
+pub fn package_license(data: &PackageJsonData) -> LicenseInfo {
+    if let Some(expression) = &data.license {
+        return LicenseInfo {
+            expression: Some(expression.clone()),
+            source: Some(LicenseSource::PackageJson),
+        };
+    }
+
+    match detect_license_file(&data.install_path) {
+        Some(expression) => LicenseInfo {
+            expression: Some(expression.to_string()),
+            source: Some(LicenseSource::LicenseFile),
+        },
+        None => LicenseInfo {
+            expression: None,
+            source: None,
+        },
+    }
+}
+
+fn detect_license_file(folder: &Path) -> Option<&'static str> {
+    let entries = fs::read_dir(folder).ok()?;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_uppercase();
+        if !["LICENSE", "LICENCE", "COPYING"]
+            .iter()
+            .any(|prefix| file_name.starts_with(prefix))
+        {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some((id, _)) = LICENSE_TEXTS
+            .iter()
+            .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        {
+            return Some(id);
+        }
+    }
+    None
+}
+
+/// A parsed SPDX license expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpdxExpression {
+    License(String),
+    And(Vec<SpdxExpression>),
+    Or(Vec<SpdxExpression>),
+}
+
+impl SpdxExpression {
+    /// Parses an expression such as `(MIT OR Apache-2.0) AND BSD-3-Clause`.
+    /// Anything that isn't a valid expression becomes a single license id.
+    pub fn parse(expression: &str) -> Self {
+        let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+        let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+        let mut pos = 0;
+        match Self::parse_or(&tokens, &mut pos) {
+            Some(parsed) if pos == tokens.len() => parsed,
+            _ => Self::License(expression.trim().to_string()),
+        }
+    }
+
+    fn parse_or(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let mut terms = vec![Self::parse_and(tokens, pos)?];
+        while tokens
+            .get(*pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case("OR"))
+        {
+            *pos += 1;
+            terms.push(Self::parse_and(tokens, pos)?);
+        }
+        Some(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Self::Or(terms)
+        })
+    }
+
+    fn parse_and(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let mut terms = vec![Self::parse_atom(tokens, pos)?];
+        while tokens
+            .get(*pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case("AND"))
+        {
+            *pos += 1;
+            terms.push(Self::parse_atom(tokens, pos)?);
+        }
+        Some(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Self::And(terms)
+        })
+    }
+
+    fn parse_atom(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let token = *tokens.get(*pos)?;
+        *pos += 1;
+        let atom = match token {
+            "(" => {
+                let inner = Self::parse_or(tokens, pos)?;
+                (*tokens.get(*pos)? == ")").then_some(())?;
+                *pos += 1;
+                inner
+            }
+            ")" => return None,
+            t if ["AND", "OR", "WITH"]
+                .iter()
+                .any(|op| t.eq_ignore_ascii_case(op)) =>
+            {
+                return None;
+            }
+            id => Self::License(id.to_string()),
+        };
+
+        // Exceptions don't change which license applies
+        if tokens
+            .get(*pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case("WITH"))
+        {
+            tokens.get(*pos + 1)?;
+            *pos += 2;
+        }
+        Some(atom)
+    }
+
+    /// Returns true if both expressions name the same licenses, ignoring the
+    /// order of terms and how license ids are spelled
+    pub fn same_as(&self, other: &Self) -> bool {
+        let same_terms = |a: &[Self], b: &[Self]| {
+            a.len() == b.len() && a.iter().all(|a| b.iter().any(|b| a.same_as(b)))
+        };
+        match (self, other) {
+            (Self::License(a), Self::License(b)) => {
+                normalize_license_id(a) == normalize_license_id(b)
+            }
+            (Self::And(a), Self::And(b)) | (Self::Or(a), Self::Or(b)) => same_terms(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// A license policy, read from a JSON file such as
+/// `{ "allow": ["MIT", "Apache-2.0"], "deny": ["GPL-3.0-only"], "ignore": ["internal-pkg"] }`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LicensePolicy {
+    /// If not empty, only these licenses are allowed
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Package names that are exempt from the policy
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl LicensePolicy {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| eyre!("Unable to read license policy {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Whether the policy allows a license or a part of a license expression.
+    /// Entries are expressions themselves, so `"MIT AND BSD-3-Clause"` allows
+    /// exactly that combination. Otherwise an expression is allowed if it can
+    /// be satisfied using allowed licenses only.
+    fn allows(&self, term: &SpdxExpression) -> bool {
+        let matches = |entry: &String| SpdxExpression::parse(entry).same_as(term);
+        if self.deny.iter().any(matches) {
+            return false;
+        }
+        if self.allow.iter().any(matches) {
+            return true;
+        }
+        match term {
+            SpdxExpression::License(_) => self.allow.is_empty(),
+            SpdxExpression::And(terms) => terms.iter().all(|t| self.allows(t)),
+            SpdxExpression::Or(terms) => terms.iter().any(|t| self.allows(t)),
+        }
+    }
+
+    /// Returns the reason a license violates the policy, if it does
+    pub fn violation(&self, license: &LicenseInfo) -> Option<String> {
+        match &license.expression {
+            None if self.allow.is_empty() => None,
+            None => Some("license is unknown".to_string()),
+            Some(expression) => (!self.allows(&SpdxExpression::parse(expression)))
+                .then(|| format!("{} is not allowed by the license policy", expression)),
+        }
+    }
+}
+
+/// Normalizes the ways packages spell the same license, e.g. `GPL-2.0+` and
+/// `GPL-2.0-or-later` become `gpl-2.0-or-later`, while `GPL-2.0-only` and the
+/// deprecated `GPL-2.0` become `gpl-2.0`
+fn normalize_license_id(id: &str) -> String {
+    let id = id.trim().to_lowercase();
+    if let Some(id) = id.strip_suffix('+') {
+        format!("{}-or-later", id)
+    } else {
+        id.strip_suffix("-only").unwrap_or(&id).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicensedPackage {
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    pub license: LicenseInfo,
+}
+
+impl fmt::Display for LicensedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.package)?;
+        if self.license.source == Some(LicenseSource::LicenseFile) {
+            write!(f, " {}", "(from license file)".bright_black())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseViolation {
+    pub package: LicensedPackage,
+    pub reason: String,
+}
+
+impl fmt::Display for LicenseViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.package.package, self.reason.red())
+    }
+}
+
+/// Collects the licenses of all installed (not first-party) packages in the graph
+pub fn collect_licenses(graph: &DependencyGraph) -> Vec<LicensedPackage> {
+    let mut seen = HashSet::new();
+    graph
+        .packages()
+        .filter(|package| !package.data.is_first_party())
+        .filter(|package| seen.insert(package.data.install_path.clone()))
+        .map(|package| LicensedPackage {
+            package: PackageKey::from(package.as_ref()),
+            install_path: package.data.install_path.clone(),
+            license: package_license(&package.data),
+        })
+        .collect()
+}
+
+/// Groups packages by license expression (None for unknown licenses)
+pub fn inventory(packages: &[LicensedPackage]) -> BTreeMap<Option<String>, Vec<&LicensedPackage>> {
+    let mut inventory: BTreeMap<Option<String>, Vec<&LicensedPackage>> = BTreeMap::new();
+    for package in packages {
+        inventory
+            .entry(package.license.expression.clone())
+            .or_default()
+            .push(package);
+    }
+    for packages in inventory.values_mut() {
+        packages.sort_by(|a, b| {
+            (&a.package.name, &a.package.version).cmp(&(&b.package.name, &b.package.version))
+        });
+    }
+    inventory
+}
+
+pub fn check_policy(packages: &[LicensedPackage], policy: &LicensePolicy) -> Vec<LicenseViolation> {
+    packages
+        .iter()
+        .filter(|package| !policy.ignore.contains(&package.package.name))
+        .filter_map(|package| {
+            Some(LicenseViolation {
+                reason: policy.violation(&package.license)?,
+                package: package.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spdx() {
+        use SpdxExpression::*;
+        assert_eq!(SpdxExpression::parse("MIT"), License("MIT".into()));
+        assert_eq!(
+            SpdxExpression::parse("(MIT OR Apache-2.0) AND BSD-3-Clause"),
+            And(vec![
+                Or(vec![License("MIT".into()), License("Apache-2.0".into())]),
+                License("BSD-3-Clause".into())
+            ])
+        );
+        assert_eq!(
+            SpdxExpression::parse("GPL-2.0-or-later WITH Classpath-exception-2.0"),
+            License("GPL-2.0-or-later".into())
+        );
+        assert_eq!(
+            SpdxExpression::parse("SEE LICENSE IN LICENSE.md"),
+            License("SEE LICENSE IN LICENSE.md".into())
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = LicensePolicy {
+            allow: vec!["MIT".into(), "Apache-2.0".into()],
+            deny: vec!["GPL-3.0".into()],
+            ignore: vec![],
+        };
+        let license = |expression: Option<&str>| LicenseInfo {
+            expression: expression.map(|e| e.to_string()),
+            source: Some(LicenseSource::PackageJson),
+        };
+        assert!(policy.violation(&license(Some("MIT"))).is_none());
+        assert!(
+            policy
+                .violation(&license(Some("(MIT OR GPL-3.0-only)")))
+                .is_none()
+        );
+        assert!(
+            policy
+                .violation(&license(Some("MIT AND GPL-3.0+")))
+                .is_some()
+        );
+        assert!(policy.violation(&license(Some("ISC"))).is_some());
+        assert!(policy.violation(&license(None)).is_some());
+
+        let deny_only = LicensePolicy {
+            deny: vec!["GPL-3.0".into()],
+            ..Default::default()
+        };
+        assert!(deny_only.violation(&license(Some("ISC"))).is_none());
+        assert!(
+            deny_only
+                .violation(&license(Some("GPL-3.0-only")))
+                .is_some()
+        );
+        assert!(deny_only.violation(&license(None)).is_none());
+        assert!(
+            deny_only
+                .violation(&license(Some("GPL-3.0-or-later")))
+                .is_none()
+        );
+
+        let expressions = LicensePolicy {
+            allow: vec!["MIT".into(), "Apache-2.0 AND LGPL-2.1-only".into()],
+            deny: vec!["GPL-2.0+".into()],
+            ignore: vec![],
+        };
+        assert!(
+            expressions
+                .violation(&license(Some("(LGPL-2.1 AND Apache-2.0) OR ISC")))
+                .is_none()
+        );
+        assert!(
+            expressions
+                .violation(&license(Some("LGPL-2.1-only")))
+                .is_some()
+        );
+        assert!(
+            expressions
+                .violation(&license(Some("MIT AND GPL-2.0-or-later")))
+                .is_some()
+        );
+        assert!(
+            expressions
+                .violation(&license(Some("MIT AND GPL-2.0-only")))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{graph::DependencyGraph, licenses, package::PackageKey};
+
+/// One installed copy of a package, as a row of `list`
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub package: PackageKey,
+    pub install_path: PathBuf,
+    /// The length of the shortest path from a root
+    pub depth: usize,
+    /// How many installed packages depend on this copy
+    pub dependents: usize,
+    /// Only reachable through devDependencies
+    pub dev_only: bool,
+    pub license: Option<String>,
+    /// Bytes on disk, not counting nested node_modules
+    pub size: u64,
+}
+
+/// Lists every installed (not first-party) package copy in the graph, sorted
+/// by name and version. `production` is the graph without dev dependencies.
+pub fn list_installed(
+    graph: &DependencyGraph,
+    production: &DependencyGraph,
+) -> Vec<InstalledPackage> {
+    // The same folder can be reached under several keys, so copies are merged by path
+    let mut by_path: HashMap<PathBuf, (InstalledPackage, HashSet<PathBuf>)> = HashMap::new();
+
+    for package in graph.packages() {
+        if package.data.is_first_party() {
+            continue;
+        }
+        let key = PackageKey::from(package.as_ref());
+        let depth = graph.depth(&key).unwrap_or_default();
+        let dependents = graph
+            .dependents(&key)
+            .iter()
+            .filter_map(|dependent| graph.get(dependent))
+            .map(|dependent| dependent.data.install_path.clone())
+            .collect::<HashSet<_>>();
+        let dev_only = production.get(&key).is_none();
+
+        let (row, all_dependents) = by_path
+            .entry(package.data.install_path.clone())
+            .or_insert_with(|| {
+                (
+                    InstalledPackage {
+                        package: key.clone(),
+                        install_path: package.data.install_path.clone(),
+                        depth,
+                        dependents: 0,
+                        dev_only,
+                        license: licenses::package_license(&package.data).expression,
+                        size: folder_size(&package.data.install_path),
+                    },
+                    HashSet::new(),
+                )
+            });
+        row.depth = row.depth.min(depth);
+        row.dev_only &= dev_only;
+        all_dependents.extend(dependents);
+        row.dependents = all_dependents.len();
+    }
+
+    let mut rows = by_path
+        .into_values()
+        .map(|(row, _)| row)
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| {
+        (&a.package.name, &a.package.version, &a.install_path).cmp(&(
+            &b.package.name,
+            &b.package.version,
+            &b.install_path,
+        ))
+    });
+    rows
+}
+
+/// The total size of the files in a package folder, skipping its own
+/// node_modules (which holds other packages) and not following symlinks
+fn folder_size(folder: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let Ok(metadata) = entry.path().symlink_metadata() else {
+                return 0;
+            };
+            if metadata.is_dir() {
+                if entry.file_name() == "node_modules" {
+                    0
+                } else {
+                    folder_size(&entry.path())
+                }
+            } else if metadata.is_file() {
+                metadata.len()
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_list_installed() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/listing")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let production = DependencyGraph::from_roots(&roots, false);
+
+        // lib is reached under two keys, as a member and through app, but
+        // both copies live in the same folder
+        let libs = graph
+            .packages()
+            .filter(|package| package.name == "lib")
+            .collect::<Vec<_>>();
+        assert_eq!(libs.len(), 2);
+        assert_eq!(libs[0].data.install_path, libs[1].data.install_path);
+
+        // The workspace members are first party, and lib counts once among
+        // shared's dependents although it is reached as a member and through app
+        let rows = list_installed(&graph, &production)
+            .into_iter()
+            .map(|row| {
+                format!(
+                    "{} depth={} dependents={} dev_only={}",
+                    row.package, row.depth, row.dependents, row.dev_only
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                "deep@1.0.0 depth=1 dependents=2 dev_only=false",
+                "devtool@1.0.0 depth=1 dependents=1 dev_only=true",
+                "shared@1.0.0 depth=1 dependents=3 dev_only=false",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folder_size() {
+        // The nested node_modules and the main.js symlink aren't counted
+        let shared = Path::new("tests/listing/node_modules/shared");
+        let files = ["package.json", "index.js"]
+            .iter()
+            .map(|file| fs::metadata(shared.join(file)).unwrap().len())
+            .sum::<u64>();
+        assert!(shared.join("node_modules").is_dir());
+        assert!(shared.join("main.js").is_symlink());
+        assert_eq!(folder_size(shared), files);
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+/// What npm recorded about an installed package
+#[derive(Debug, Clone, Default)]
+pub struct LockedPackage {
+    /// Where the package was fetched from, e.g. a registry tarball URL
+    pub resolved: Option<String>,
+    /// The Subresource Integrity of the tarball
+    pub integrity: Option<String>,
+}
+
+/// Reads the hidden lockfile npm keeps in node_modules, falling back to
+/// package-lock.json. Entries are keyed by install path. Projects installed
+/// by other package managers have neither, so this is empty for them.
+pub fn read_locked_packages(root: &Path) -> HashMap<PathBuf, LockedPackage> {
+    let lockfile = [
+        root.join("node_modules").join(".package-lock.json"),
+        root.join("package-lock.json"),
+    ]
+    .into_iter()
+    .find_map(|path| fs::read_to_string(path).ok())
+    .and_then(|contents| serde_json::from_str::<Value>(&contents).ok());
+
+    let Some(packages) = lockfile
+        .as_ref()
+        .and_then(|lockfile| lockfile.get("packages"))
+        .and_then(|packages| packages.as_object())
+    else {
+        return HashMap::new();
+    };
+
+    let field = |entry: &Value, name: &str| {
+        entry
+            .get(name)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    };
+    packages
+        .iter()
+        .filter(|(path, _)| !path.is_empty())
+        .map(|(path, entry)| {
+            (
+                root.join(path),
+                LockedPackage {
+                    resolved: field(entry, "resolved"),
+                    integrity: field(entry, "integrity"),
+                },
+            )
+        })
+        .collect()
+}
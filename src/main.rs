@@ -1,20 +1,51 @@
+use audit::AdvisoryDatabase;
 use clap::Parser;
 use color_eyre::eyre::{Result, eyre};
 use colored::*;
-use diff::Differ;
+use dependency_resolver::Cycle;
+use diff::{ChangeKind, DiffFilter, Differ};
+use engines::TargetNode;
+use graph::DependencyGraph;
+use licenses::LicensePolicy;
+use output::{CheckFormat, Diagram, DiffFormat, ListFormat, NodeStatus, SbomFormat, TreeFormat};
+use package::{Package, PackageKey};
 use ptree::{PrintConfig, Style as PStyle};
+use registry::Registry;
 use resolver::Resolver;
-use std::path::PathBuf;
+use semver::Version;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::rc::Rc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use version_bump::Bump;
 
+mod audit;
+mod check;
 mod dependency_resolver;
+mod deprecations;
 mod diff;
+mod diff3;
+mod engines;
 mod extended_version_req;
+mod graph;
+mod json_positions;
+mod licenses;
+mod listing;
+mod lockfile;
 mod node_modules;
+mod outdated;
+mod output;
 mod package;
 mod package_data;
+mod phantoms;
 mod ptree_impl;
+mod registry;
 mod resolver;
+mod sbom;
+mod source_scan;
+mod unused;
+mod version_bump;
 mod workspace_data;
 
 #[derive(Parser, Debug)]
@@ -30,9 +61,220 @@ struct Args {
 #[derive(Parser, Debug)]
 enum Commands {
     /// Show the dependency tree for a package
-    Tree { packages: Vec<PathBuf> },
+    Tree {
+        packages: Vec<PathBuf>,
+
+        /// A registry URL or folder of cached packuments (npm's _cacache or <name>.json files) used to mark deprecated packages
+        #[arg(long)]
+        registry: Option<String>,
+
+        /// Print the tree as text, the deduplicated graph as Graphviz DOT, or JSON in the schema of `npm ls --all --json`
+        #[arg(long, value_enum, default_value_t = TreeFormat::Text)]
+        format: TreeFormat,
+    },
     /// Compare dependencies between two packages
-    Diff { left: PathBuf, right: PathBuf },
+    Diff {
+        left: PathBuf,
+        right: PathBuf,
+
+        /// Only show changes of these kinds
+        #[arg(long, value_enum)]
+        only: Vec<ChangeKind>,
+
+        /// Print totals and a flat list of changed packages instead of the tree
+        #[arg(long)]
+        summary: bool,
+
+        /// Only show changes to dependencies whose names match these globs, e.g. '@types/*'. Non-matching dependencies are kept as the path to a match
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// Hide dependencies whose names match these globs, along with their subtrees
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Hide the root's devDependencies
+        #[arg(long, conflicts_with = "dev")]
+        prod: bool,
+
+        /// Only show the root's devDependencies, with all of their dependencies
+        #[arg(long)]
+        dev: bool,
+
+        /// Hide dependencies deeper than this below the root
+        #[arg(long)]
+        max_depth: Option<usize>,
+
+        /// Print the diff tree as text, the changed part of the graph as Graphviz DOT or a Mermaid diagram, or a Markdown report
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text, conflicts_with = "summary")]
+        format: DiffFormat,
+
+        /// The most packages to draw in a Mermaid diagram
+        #[arg(long, default_value_t = 50)]
+        max_nodes: usize,
+    },
+    /// Compare two packages against a common base, e.g. to resolve lockfile merge conflicts
+    Diff3 {
+        base: PathBuf,
+        ours: PathBuf,
+        theirs: PathBuf,
+
+        /// Print the changed dependencies as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write a report for sharing with people who don't run the CLI
+    Report {
+        packages: Vec<PathBuf>,
+
+        /// Write a self-contained HTML page with a searchable, collapsible tree to this file
+        #[arg(long)]
+        html: PathBuf,
+    },
+    /// List every installed package copy as one row of a spreadsheet
+    List {
+        packages: Vec<PathBuf>,
+
+        /// Separate the columns with commas or tabs
+        #[arg(long, value_enum, default_value_t = ListFormat::Csv)]
+        format: ListFormat,
+    },
+    /// Write a software bill of materials for the installed packages
+    Sbom {
+        packages: Vec<PathBuf>,
+
+        /// The document format to print
+        #[arg(long, value_enum, default_value_t = SbomFormat::Cyclonedx)]
+        format: SbomFormat,
+
+        /// Include packages only reachable through devDependencies
+        #[arg(long)]
+        dev: bool,
+    },
+    /// List the dependency cycles found while resolving packages
+    Cycles {
+        packages: Vec<PathBuf>,
+
+        /// Print the cycles as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List imported packages that are not declared but resolve through hoisting
+    Phantoms {
+        packages: Vec<PathBuf>,
+
+        /// Print the phantom dependencies as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List declared dependencies that nothing imports, configures or runs
+    Unused {
+        packages: Vec<PathBuf>,
+
+        /// Print the unused dependencies as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List installed packages whose engines.node excludes a Node version
+    Engines {
+        packages: Vec<PathBuf>,
+
+        /// The Node version to check against [default: the root's engines.node, .nvmrc or .node-version]
+        #[arg(long, value_parser = parse_node_arg)]
+        node: Option<Version>,
+
+        /// Print the violations as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print an inventory of the licenses of installed packages
+    Licenses {
+        packages: Vec<PathBuf>,
+
+        /// Include packages only reachable through devDependencies
+        #[arg(long)]
+        dev: bool,
+
+        /// A JSON license policy to report violations of
+        #[arg(long)]
+        policy: Option<PathBuf>,
+
+        /// Print the inventory as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that installed packages satisfy their declared dependencies, failing if not
+    Check {
+        packages: Vec<PathBuf>,
+
+        /// A JSON license policy that installed production dependencies must satisfy
+        #[arg(long)]
+        license_policy: Option<PathBuf>,
+
+        /// Also check that installed packages' engines.node allows this Node version
+        #[arg(long, value_parser = parse_node_arg)]
+        node: Option<Version>,
+
+        /// Also check that peerDependencies are installed in a satisfying version
+        #[arg(long)]
+        peers: bool,
+
+        /// Print the problems as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Print the problems as text, as a SARIF log for code scanning or as JUnit XML
+        #[arg(long, value_enum, default_value_t = CheckFormat::Text, conflicts_with = "json")]
+        format: CheckFormat,
+    },
+    /// Match installed packages against a local directory of OSV advisories
+    Audit {
+        packages: Vec<PathBuf>,
+
+        /// A folder of OSV JSON files
+        #[arg(long)]
+        db: PathBuf,
+
+        /// Skip packages only reachable through devDependencies
+        #[arg(long)]
+        prod: bool,
+
+        /// Print the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List installed packages marked deprecated in local registry metadata
+    Deprecated {
+        packages: Vec<PathBuf>,
+
+        /// A registry URL or folder of cached packuments: npm's _cacache or <name>.json files
+        #[arg(long)]
+        registry: String,
+
+        /// Skip packages only reachable through devDependencies
+        #[arg(long)]
+        prod: bool,
+
+        /// Print the deprecated packages as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List direct dependencies with newer versions in a local registry mirror
+    Outdated {
+        packages: Vec<PathBuf>,
+
+        /// A registry URL (e.g. a local verdaccio) or folder of cached packuments
+        #[arg(long)]
+        registry: String,
+
+        /// Print the outdated dependencies as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn parse_node_arg(version: &str) -> Result<Version, String> {
+    engines::parse_node_version(version).ok_or_else(|| format!("invalid node version: {}", version))
 }
 
 fn install_tracing() {
@@ -42,7 +284,7 @@ fn install_tracing() {
         .init();
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     color_eyre::install()?;
     install_tracing();
 
@@ -58,58 +300,1108 @@ fn main() -> Result<()> {
     };
 
     match args.command {
-        Commands::Tree { packages } => handle_tree_command(packages, config),
+        Commands::Tree {
+            packages,
+            registry,
+            format,
+        } => handle_tree_command(packages, registry, format, config),
         Commands::Diff {
             left: first,
             right: second,
-        } => handle_diff_command(first, second, config),
+            only,
+            summary,
+            include,
+            exclude,
+            prod,
+            dev,
+            max_depth,
+            format,
+            max_nodes,
+        } => {
+            let filter = DiffFilter {
+                only,
+                include: (!include.is_empty())
+                    .then(|| source_scan::build_globset(&include))
+                    .transpose()?,
+                exclude: (!exclude.is_empty())
+                    .then(|| source_scan::build_globset(&exclude))
+                    .transpose()?,
+                prod,
+                dev,
+                max_depth,
+            };
+            handle_diff_command(first, second, filter, summary, format, max_nodes, config)
+        }
+        Commands::Diff3 {
+            base,
+            ours,
+            theirs,
+            json,
+        } => handle_diff3_command(base, ours, theirs, json, config),
+        Commands::Report { packages, html } => handle_report_command(packages, html, config),
+        Commands::List { packages, format } => handle_list_command(packages, format, config),
+        Commands::Sbom {
+            packages,
+            format,
+            dev,
+        } => handle_sbom_command(packages, format, dev, config),
+        Commands::Cycles { packages, json } => handle_cycles_command(packages, json, config),
+        Commands::Phantoms { packages, json } => handle_phantoms_command(packages, json, config),
+        Commands::Unused { packages, json } => handle_unused_command(packages, json, config),
+        Commands::Engines {
+            packages,
+            node,
+            json,
+        } => handle_engines_command(packages, node, json, config),
+        Commands::Licenses {
+            packages,
+            dev,
+            policy,
+            json,
+        } => handle_licenses_command(packages, dev, policy, json, config),
+        Commands::Check {
+            packages,
+            license_policy,
+            node,
+            peers,
+            json,
+            format,
+        } => {
+            return handle_check_command(
+                packages,
+                license_policy,
+                node,
+                peers,
+                json,
+                format,
+                config,
+            );
+        }
+        Commands::Audit {
+            packages,
+            db,
+            prod,
+            json,
+        } => return handle_audit_command(packages, db, prod, json, config),
+        Commands::Deprecated {
+            packages,
+            registry,
+            prod,
+            json,
+        } => handle_deprecated_command(packages, registry, prod, json, config),
+        Commands::Outdated {
+            packages,
+            registry,
+            json,
+        } => handle_outdated_command(packages, registry, json, config),
+    }?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolves a package, followed by its workspace members if it is a workspace root
+fn resolve_with_members(resolver: &mut Resolver, path: &Path) -> Result<Vec<Rc<Package>>> {
+    let package = resolver.resolve(path)?;
+    let mut packages = vec![package.clone()];
+
+    if let Some(workspace_data) = &package.data.workspace_data {
+        packages.extend(resolver.resolve_workspace_members(path, workspace_data)?);
+    }
+
+    Ok(packages)
+}
+
+fn handle_tree_command(
+    packages: Vec<PathBuf>,
+    registry: Option<String>,
+    format: TreeFormat,
+    config: PrintConfig,
+) -> Result<()> {
+    let registry = registry
+        .map(|location| Registry::open(&location))
+        .transpose()?;
+    let mut resolver = Resolver::new(config.depth as usize);
+
+    if format == TreeFormat::Dot {
+        // All packages share one graph so that common dependencies are a single node
+        let mut roots = Vec::new();
+        for package_path in packages {
+            roots.extend(resolve_with_members(&mut resolver, &package_path)?);
+        }
+        let graph = DependencyGraph::from_roots(&roots, true);
+        print!("{}", output::dot::render(&Diagram::from_graph(&graph)));
+        return Ok(());
+    }
+
+    if format == TreeFormat::NpmJson {
+        // Prints exactly what `npm ls --all --json` would, which describes one project
+        let [package_path] = packages.as_slice() else {
+            return Err(eyre!("--format npm-json takes a single package"));
+        };
+        let resolved = resolve_with_members(&mut resolver, package_path)?;
+        let value = output::npm_ls::render(&resolved[0], &resolved[1..]);
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    for package_path in packages {
+        let resolved = resolve_with_members(&mut resolver, &package_path)?;
+        if let Some(registry) = &registry {
+            let graph = DependencyGraph::from_roots(&resolved, true);
+            deprecations::find_deprecated(&graph, registry)?;
+        }
+        let is_workspace = resolved[0].data.is_workspace_root();
+
+        for (i, package) in resolved.iter().enumerate() {
+            if is_workspace {
+                if i == 0 {
+                    println!("{}", "[WORKSPACE ROOT]".blue());
+                } else {
+                    println!("{}", "[WORKSPACE MEMBER]".blue());
+                }
+            }
+
+            package
+                .print_tree(&config)
+                .expect("Unable to print dependency tree");
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_report_command(packages: Vec<PathBuf>, html: PathBuf, config: PrintConfig) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut roots = Vec::new();
+    for package_path in packages {
+        roots.extend(resolve_with_members(&mut resolver, &package_path)?);
+    }
+
+    std::fs::write(&html, output::html::render(&roots)?)?;
+    println!("{} {}", "Report written to".green(), html.display());
+    Ok(())
+}
+
+fn handle_list_command(
+    packages: Vec<PathBuf>,
+    format: ListFormat,
+    config: PrintConfig,
+) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut roots = Vec::new();
+    for package_path in packages {
+        roots.extend(resolve_with_members(&mut resolver, &package_path)?);
+    }
+
+    let graph = DependencyGraph::from_roots(&roots, true);
+    let production = DependencyGraph::from_roots(&roots, false);
+    let rows = listing::list_installed(&graph, &production);
+    let separator = match format {
+        ListFormat::Csv => ',',
+        ListFormat::Tsv => '\t',
+    };
+    print!("{}", output::csv::render(&rows, separator));
+    Ok(())
+}
+
+fn handle_sbom_command(
+    packages: Vec<PathBuf>,
+    format: SbomFormat,
+    dev: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut roots = Vec::new();
+    for package_path in packages {
+        roots.extend(resolve_with_members(&mut resolver, &package_path)?);
     }
+
+    let graph = DependencyGraph::from_roots(&roots, dev);
+    let production = DependencyGraph::from_roots(&roots, false);
+    let components = sbom::collect_components(&graph, &production);
+    match format {
+        SbomFormat::Cyclonedx => println!("{}", output::cyclonedx::render(&components)?),
+        SbomFormat::SpdxJson => println!("{}", output::spdx::render_json(&components)?),
+        SbomFormat::SpdxTagValue => print!("{}", output::spdx::render_tag_value(&components)?),
+    }
+    Ok(())
 }
 
-fn handle_tree_command(packages: Vec<PathBuf>, config: PrintConfig) -> Result<()> {
+fn handle_cycles_command(packages: Vec<PathBuf>, json: bool, config: PrintConfig) -> Result<()> {
     let mut resolver = Resolver::new(config.depth as usize);
+    let mut cycles: Vec<Cycle> = Vec::new();
 
     for package_path in packages {
-        let package = resolver.resolve(&package_path)?;
+        let resolved = resolve_with_members(&mut resolver, &package_path)?;
+        let dep_resolver = resolved[0]
+            .resolver()
+            .ok_or(eyre!("Dependency resolver is missing"))?;
+
+        for cycle in dep_resolver.cycles() {
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+        }
+    }
+
+    cycles.sort_by_cached_key(|cycle| {
+        cycle
+            .packages
+            .iter()
+            .map(|key| (key.name.clone(), key.version.clone()))
+            .collect::<Vec<_>>()
+    });
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "cycles": cycles }))?
+        );
+    } else if cycles.is_empty() {
+        println!("{}", "No dependency cycles found".green());
+    } else {
+        for cycle in &cycles {
+            println!("{}", cycle);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_diff_command(
+    left: PathBuf,
+    right: PathBuf,
+    filter: DiffFilter,
+    summary: bool,
+    format: DiffFormat,
+    max_nodes: usize,
+    config: PrintConfig,
+) -> Result<()> {
+    // let mut workspace_resolver = WorkspaceResolver::new(config.depth as usize);
+    let mut resolver = Resolver::new(config.depth as usize);
+
+    let left_packages = resolve_with_members(&mut resolver, &left)?;
+    let right_packages = resolve_with_members(&mut resolver, &right)?;
+    let is_workspace =
+        left_packages[0].data.is_workspace_root() && right_packages[0].data.is_workspace_root();
+
+    if format != DiffFormat::Text {
+        let members = if is_workspace {
+            paired_members(&left_packages, &right_packages)
+        } else {
+            Vec::new()
+        };
+
+        let mut differs = vec![
+            Differ::diff(
+                left_packages[0].clone(),
+                right_packages[0].clone(),
+                filter.clone(),
+            )?
+            .0,
+        ];
+        for member in &members {
+            if let (Some(left), Some(right)) = *member {
+                differs.push(Differ::diff(left.clone(), right.clone(), filter.clone())?.0);
+            }
+        }
+
+        let (added_members, removed_members) = unpaired_members(&members);
+
+        if format == DiffFormat::Markdown {
+            print!(
+                "{}",
+                output::markdown::render(&differs, &added_members, &removed_members)
+            );
+            return Ok(());
+        }
+
+        let mut diagram = Diagram::from_diffs(&differs);
+        for package in removed_members {
+            diagram.add_package(package, NodeStatus::Removed);
+        }
+        for package in added_members {
+            diagram.add_package(package, NodeStatus::Added);
+        }
+
+        if format == DiffFormat::Mermaid {
+            diagram.collapse_unchanged();
+            diagram.truncate(max_nodes);
+            print!("{}", output::mermaid::render(&diagram));
+        } else {
+            print!("{}", output::dot::render(&diagram));
+        }
+        return Ok(());
+    }
 
-        if package.data.is_workspace_root() {
-            println!("{}", "[WORKSPACE ROOT]".blue());
+    if !is_workspace {
+        let differ = diff_pair(
+            &left_packages[0],
+            &right_packages[0],
+            &filter,
+            summary,
+            &config,
+        )?;
+        if !summary {
+            print_bump_summary(&Differ::bump_counts(&[differ]), &filter);
         }
+        return Ok(());
+    }
+
+    // Members are paired by name; the roots are always paired
+    let mut differs = Vec::new();
+    println!("{}", "[WORKSPACE ROOT]".blue());
+    differs.push(diff_pair(
+        &left_packages[0],
+        &right_packages[0],
+        &filter,
+        summary,
+        &config,
+    )?);
+
+    for (left, right) in paired_members(&left_packages, &right_packages) {
+        println!();
+        println!("{}", "[WORKSPACE MEMBER]".blue());
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                differs.push(diff_pair(left, right, &filter, summary, &config)?);
+            }
+            (Some(left), None) => println!("{} {}", "[REMOVED]".red(), left),
+            (None, Some(right)) => println!("{} {}", "[ADDED]".green(), right),
+            (None, None) => unreachable!("member names come from either side"),
+        }
+    }
+
+    if !summary {
+        print_bump_summary(&Differ::bump_counts(&differs), &filter);
+    }
 
-        package
-            .print_tree(&config)
+    Ok(())
+}
+
+/// A workspace member on the left and right side of a diff
+type MemberPair<'a> = (Option<&'a Rc<Package>>, Option<&'a Rc<Package>>);
+
+/// Pairs the workspace members of both sides by name, skipping the roots
+fn paired_members<'a>(
+    left_packages: &'a [Rc<Package>],
+    right_packages: &'a [Rc<Package>],
+) -> Vec<MemberPair<'a>> {
+    let mut names = left_packages[1..]
+        .iter()
+        .chain(&right_packages[1..])
+        .map(|package| package.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .map(|name| {
+            (
+                left_packages[1..].iter().find(|p| p.name == name),
+                right_packages[1..].iter().find(|p| p.name == name),
+            )
+        })
+        .collect()
+}
+
+/// Splits off the members only one side has, as (added, removed)
+fn unpaired_members<'a>(members: &[MemberPair<'a>]) -> (Vec<&'a Package>, Vec<&'a Package>) {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for member in members {
+        match *member {
+            (None, Some(right)) => added.push(right.as_ref()),
+            (Some(left), None) => removed.push(left.as_ref()),
+            _ => {}
+        }
+    }
+    (added, removed)
+}
+
+/// Diffs two packages and prints either the diff tree or the summary
+fn diff_pair(
+    left: &Rc<Package>,
+    right: &Rc<Package>,
+    filter: &DiffFilter,
+    summary: bool,
+    config: &PrintConfig,
+) -> Result<Rc<Differ>> {
+    let (differ, diff) = Differ::diff(left.clone(), right.clone(), filter.clone())?;
+
+    if summary {
+        println!("{}", left);
+        print_diff_summary(&differ);
+    } else if let Some(diff) = diff {
+        diff.print_tree(config)
             .expect("Unable to print dependency tree");
-        println!("");
+    } else {
+        println!("{} {}", left, "no changes".green());
+    }
+
+    Ok(differ)
+}
+
+fn handle_diff3_command(
+    base: PathBuf,
+    ours: PathBuf,
+    theirs: PathBuf,
+    json: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
 
-        if let Some(workspace_data) = package.data.workspace_data.clone() {
-            for workspace_package in
-                resolver.resolve_workspace_members(&package_path, &workspace_data)?
+    let base = resolver.resolve(&base)?;
+    let ours = resolver.resolve(&ours)?;
+    let theirs = resolver.resolve(&theirs)?;
+
+    let changes = diff3::diff3(&base, &ours, &theirs)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "changes": changes }))?
+        );
+        return Ok(());
+    }
+
+    if changes.is_empty() {
+        println!("{}", "No changes on either side".green());
+        return Ok(());
+    }
+    for change in &changes {
+        println!("{}", change);
+    }
+
+    let conflicts = changes
+        .iter()
+        .filter(|c| c.classification == diff3::Classification::Conflicting)
+        .count();
+    println!();
+    if conflicts == 0 {
+        println!("{}", "No conflicting changes".green());
+    } else {
+        println!(
+            "{}",
+            format!("{} conflicting dependencies", conflicts).red()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_diff_summary(differ: &Differ) {
+    let summary = differ.summary();
+
+    for (label, count) in [
+        ("added", summary.added),
+        ("removed", summary.removed),
+        ("renamed", summary.renamed),
+        ("upgraded", summary.upgraded),
+        ("downgraded", summary.downgraded),
+        ("became missing", summary.became_missing),
+        ("became resolved", summary.became_resolved),
+        ("requirement only", summary.requirement_only),
+    ] {
+        println!("{:<18}{:>5}", format!("{}:", label), count);
+    }
+
+    let changes = summary
+        .changes
+        .iter()
+        .filter(|change| differ.filter.matches_bump(change.bump()))
+        .collect::<Vec<_>>();
+    if !changes.is_empty() {
+        println!();
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+}
+
+/// Prints how many distinct package versions changed, by kind of change
+fn print_bump_summary(counts: &HashMap<Bump, usize>, filter: &DiffFilter) {
+    let bumps = Bump::ALL
+        .into_iter()
+        .filter(|bump| filter.matches_bump(Some(*bump)))
+        .collect::<Vec<_>>();
+    if bumps.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", "Version changes".bold());
+    for bump in &bumps {
+        let count = counts.get(bump).copied().unwrap_or(0);
+        println!(
+            "  {}  {:>5}",
+            format!("{:<10}", bump.as_str()).color(bump.color()),
+            count
+        );
+    }
+    let total = bumps
+        .iter()
+        .map(|bump| counts.get(bump).copied().unwrap_or(0))
+        .sum::<usize>();
+    println!("  {}  {:>5}", format!("{:<10}", "total").bold(), total);
+}
+
+fn handle_phantoms_command(packages: Vec<PathBuf>, json: bool, config: PrintConfig) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut report = Vec::new();
+
+    for package_path in packages {
+        for package in resolve_with_members(&mut resolver, &package_path)? {
+            let phantoms = phantoms::find_phantoms(&package)?;
+            report.push((package, phantoms));
+        }
+    }
+
+    if json {
+        let value = report
+            .iter()
+            .map(|(package, phantoms)| {
+                serde_json::json!({
+                    "package": PackageKey::from(package.as_ref()),
+                    "install_path": package.data.install_path,
+                    "phantoms": phantoms,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "packages": value }))?
+        );
+        return Ok(());
+    }
+
+    for (package, phantoms) in report {
+        if phantoms.is_empty() {
+            println!("{} {}", package, "no phantom dependencies".green());
+            continue;
+        }
+
+        println!("{}", package);
+        for phantom in phantoms {
+            println!("  {}", phantom);
+            for import in &phantom.imports {
+                let file = import
+                    .file
+                    .strip_prefix(&package.data.install_path)
+                    .unwrap_or(&import.file);
+                println!(
+                    "    {}",
+                    format!("{}:{} ({})", file.display(), import.line, import.specifier)
+                        .bright_black()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_unused_command(packages: Vec<PathBuf>, json: bool, config: PrintConfig) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut report = Vec::new();
+
+    for package_path in packages {
+        for package in resolve_with_members(&mut resolver, &package_path)? {
+            let unused = unused::find_unused(&package)?;
+            report.push((package, unused));
+        }
+    }
+
+    if json {
+        let value = report
+            .iter()
+            .map(|(package, unused)| {
+                serde_json::json!({
+                    "package": PackageKey::from(package.as_ref()),
+                    "install_path": package.data.install_path,
+                    "unused": unused,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "packages": value }))?
+        );
+        return Ok(());
+    }
+
+    for (package, unused) in report {
+        if unused.is_empty() {
+            println!("{} {}", package, "no unused dependencies".green());
+            continue;
+        }
+
+        println!("{}", package);
+        for name in &unused.dependencies {
+            println!("  {}", name.yellow());
+        }
+        if !unused.dev_dependencies.is_empty() {
+            println!("  {}", "[DEV DEPENDENCIES]".blue());
+            for name in &unused.dev_dependencies {
+                println!("  {}", name.yellow());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_engines_command(
+    packages: Vec<PathBuf>,
+    node: Option<Version>,
+    json: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut report = Vec::new();
+
+    for package_path in packages {
+        let roots = resolve_with_members(&mut resolver, &package_path)?;
+        let target = match &node {
+            Some(version) => TargetNode {
+                version: version.clone(),
+                source: "--node".to_string(),
+            },
+            None => engines::default_target_node(&roots[0].data)?,
+        };
+
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let violations = engines::check_engines(&graph, &target.version);
+        report.push((roots[0].clone(), target, violations));
+    }
+
+    if json {
+        let value = report
+            .iter()
+            .map(|(package, target, violations)| {
+                serde_json::json!({
+                    "package": PackageKey::from(package.as_ref()),
+                    "node": target,
+                    "violations": violations,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "packages": value }))?
+        );
+        return Ok(());
+    }
+
+    for (package, target, violations) in report {
+        println!(
+            "{} {}",
+            package,
+            format!("(node {} from {})", target.version, target.source).bright_black()
+        );
+        if violations.is_empty() {
+            println!("{}", "All packages support this node version".green());
+        }
+        for violation in violations {
+            println!("{}", violation);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn handle_licenses_command(
+    packages: Vec<PathBuf>,
+    dev: bool,
+    policy: Option<PathBuf>,
+    json: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let policy = policy
+        .map(|path| LicensePolicy::from_file(&path))
+        .transpose()?;
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut licensed = Vec::new();
+
+    for package_path in packages {
+        let roots = resolve_with_members(&mut resolver, &package_path)?;
+        let graph = DependencyGraph::from_roots(&roots, dev);
+        for package in licenses::collect_licenses(&graph) {
+            if !licensed
+                .iter()
+                .any(|p: &licenses::LicensedPackage| p.install_path == package.install_path)
             {
-                println!("{}", "[WORKSPACE MEMBER]".blue());
-                workspace_package
-                    .print_tree(&config)
-                    .expect("Unable to print dependency tree");
-                println!("");
+                licensed.push(package);
             }
         }
     }
 
+    let inventory = licenses::inventory(&licensed);
+    let violations = policy
+        .as_ref()
+        .map(|policy| licenses::check_policy(&licensed, policy))
+        .unwrap_or_default();
+
+    if json {
+        let value = inventory
+            .iter()
+            .map(|(license, packages)| {
+                serde_json::json!({
+                    "license": license,
+                    "count": packages.len(),
+                    "packages": packages,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "licenses": value,
+                "violations": violations,
+            }))?
+        );
+        return Ok(());
+    }
+
+    // Unknown licenses are listed last
+    for (license, packages) in inventory
+        .iter()
+        .filter(|(license, _)| license.is_some())
+        .chain(inventory.iter().filter(|(license, _)| license.is_none()))
+    {
+        let license = match license {
+            Some(license) => license.bold(),
+            None => "[UNKNOWN]".yellow().bold(),
+        };
+        println!(
+            "{} {}",
+            license,
+            format!("({})", packages.len()).bright_black()
+        );
+        for package in packages {
+            println!("  {}", package);
+        }
+    }
+
+    if policy.is_some() {
+        println!();
+        if violations.is_empty() {
+            println!("{}", "No license policy violations".green());
+        }
+        for violation in &violations {
+            println!("{}", violation);
+        }
+    }
+
     Ok(())
 }
 
-fn handle_diff_command(left: PathBuf, right: PathBuf, config: PrintConfig) -> Result<()> {
-    // let mut workspace_resolver = WorkspaceResolver::new(config.depth as usize);
+fn handle_check_command(
+    packages: Vec<PathBuf>,
+    license_policy: Option<PathBuf>,
+    node: Option<Version>,
+    peers: bool,
+    json: bool,
+    format: CheckFormat,
+    config: PrintConfig,
+) -> Result<ExitCode> {
+    let license_policy = license_policy
+        .map(|path| LicensePolicy::from_file(&path))
+        .transpose()?;
     let mut resolver = Resolver::new(config.depth as usize);
+    let mut findings = Vec::new();
+    let mut members = Vec::new();
 
-    let left_package = resolver.resolve(&left)?;
-    let right_package = resolver.resolve(&right)?;
+    for package_path in packages {
+        let roots = resolve_with_members(&mut resolver, &package_path)?;
+        let graph = DependencyGraph::from_roots(&roots, true);
+        members.extend(roots.iter().cloned());
+        let start = findings.len();
+        findings.extend(check::check_dependencies(&graph));
+        if peers {
+            findings.extend(check::check_peers(&graph));
+        }
 
-    let (_differ, diff) = Differ::diff(left_package.clone(), right_package.clone())?;
+        if let Some(node) = &node {
+            findings.extend(
+                engines::check_engines(&graph, node)
+                    .into_iter()
+                    .map(|violation| check::Finding::from_engine_violation(violation, node)),
+            );
+        }
 
-    let diff = diff.ok_or(eyre!("Unable to diff packages"))?;
+        if let Some(policy) = &license_policy {
+            // Licenses only matter for what ships, so dev-only packages are skipped
+            let production = DependencyGraph::from_roots(&roots, false);
+            let licensed = licenses::collect_licenses(&production);
+            findings.extend(
+                licenses::check_policy(&licensed, policy)
+                    .into_iter()
+                    .map(check::Finding::from),
+            );
+        }
+        check::locate(&mut findings[start..], &graph);
+    }
 
-    diff.print_tree(&config)
-        .expect("Unable to print dependency tree");
+    if format == CheckFormat::Sarif {
+        println!("{}", output::sarif::render(&findings)?);
+    } else if format == CheckFormat::Junit {
+        print!("{}", output::junit::render(&members, &findings));
+    } else if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "problems": findings }))?
+        );
+    } else if findings.is_empty() {
+        println!("{}", "No problems found".green());
+    } else {
+        for finding in &findings {
+            println!("{}", finding);
+        }
+        println!();
+        let noun = if findings.len() == 1 {
+            "problem"
+        } else {
+            "problems"
+        };
+        println!("{}", format!("{} {} found", findings.len(), noun).red());
+    }
+
+    Ok(if findings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn handle_audit_command(
+    packages: Vec<PathBuf>,
+    db: PathBuf,
+    prod: bool,
+    json: bool,
+    config: PrintConfig,
+) -> Result<ExitCode> {
+    let database = AdvisoryDatabase::from_folder(&db)?;
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut findings = Vec::new();
+
+    for package_path in packages {
+        let roots = resolve_with_members(&mut resolver, &package_path)?;
+        let graph = DependencyGraph::from_roots(&roots, !prod);
+        findings.extend(audit::audit(&graph, &database));
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "vulnerabilities": findings }))?
+        );
+    } else if findings.is_empty() {
+        println!("{}", "No known vulnerabilities found".green());
+    } else {
+        for finding in &findings {
+            println!("{}", finding);
+            println!();
+        }
+        println!(
+            "{}",
+            format!("{} vulnerable packages found", findings.len()).red()
+        );
+    }
+
+    Ok(if findings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn handle_deprecated_command(
+    packages: Vec<PathBuf>,
+    registry: String,
+    prod: bool,
+    json: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let registry = Registry::open(&registry)?;
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut deprecated = Vec::new();
+
+    for package_path in packages {
+        let roots = resolve_with_members(&mut resolver, &package_path)?;
+        let graph = DependencyGraph::from_roots(&roots, !prod);
+        deprecated.extend(deprecations::find_deprecated(&graph, &registry)?);
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "deprecated": deprecated }))?
+        );
+    } else if deprecated.is_empty() {
+        println!("{}", "No deprecated packages found".green());
+    } else {
+        for package in &deprecated {
+            println!("{}", package);
+            println!();
+        }
+        println!(
+            "{}",
+            format!("{} deprecated packages found", deprecated.len()).yellow()
+        );
+    }
+
+    Ok(())
+}
+
+fn handle_outdated_command(
+    packages: Vec<PathBuf>,
+    registry: String,
+    json: bool,
+    config: PrintConfig,
+) -> Result<()> {
+    let registry = Registry::open(&registry)?;
+    let mut resolver = Resolver::new(config.depth as usize);
+    let mut report = Vec::new();
+
+    for package_path in packages {
+        for package in resolve_with_members(&mut resolver, &package_path)? {
+            let outdated = outdated::find_outdated(&package, &registry)?;
+            report.push((package, outdated));
+        }
+    }
+
+    if json {
+        let value = report
+            .iter()
+            .map(|(package, outdated)| {
+                serde_json::json!({
+                    "package": PackageKey::from(package.as_ref()),
+                    "install_path": package.data.install_path,
+                    "outdated": outdated,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "packages": value }))?
+        );
+        return Ok(());
+    }
+
+    let version = |v: &Option<Version>| v.as_ref().map_or("-".to_string(), |v| v.to_string());
+    for (package, outdated) in report {
+        if outdated.is_empty() {
+            println!("{} {}", package, "all dependencies up to date".green());
+            continue;
+        }
+
+        println!("{}", package);
+        let rows = outdated
+            .iter()
+            .map(|dependency| {
+                let name = if dependency.dev {
+                    format!("{} (dev)", dependency.name)
+                } else {
+                    dependency.name.clone()
+                };
+                [
+                    name,
+                    version(&dependency.current),
+                    version(&dependency.wanted),
+                    version(&dependency.latest),
+                    dependency.bump.map_or("-".to_string(), |b| b.to_string()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let header = ["Package", "Current", "Wanted", "Latest", "Bump"];
+        let widths = (0..header.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].len())
+                    .chain([header[i].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let header = header
+            .iter()
+            .zip(&widths)
+            .map(|(h, w)| format!("{:<w$}", h))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("  {}", header.trim_end().underline());
+        for (dependency, row) in outdated.iter().zip(rows) {
+            let cell = |i: usize| format!("{:<w$}", row[i], w = widths[i]);
+            // Like npm: red when a newer version is allowed by the declared
+            // range, yellow when only a range change would pick it up
+            let current = if dependency.current.is_none() || dependency.wanted > dependency.current
+            {
+                cell(1).red()
+            } else {
+                cell(1).yellow()
+            };
+            let bump = match dependency.bump {
+                Some(bump) => cell(4).color(bump.color()),
+                None => cell(4).bright_black(),
+            };
+            println!(
+                "  {}  {}  {}  {}  {}",
+                cell(0),
+                current,
+                cell(2).green(),
+                cell(3).blue(),
+                bump
+            );
+        }
+        println!();
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paired_members() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let left = resolve_with_members(&mut resolver, Path::new("tests/diff/left")).unwrap();
+        let right = resolve_with_members(&mut resolver, Path::new("tests/diff/right")).unwrap();
+
+        let members = paired_members(&left, &right);
+        let pairs = members
+            .iter()
+            .map(|(left, right)| {
+                (
+                    left.map(|package| package.to_string()),
+                    right.map(|package| package.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            [
+                (Some("app@1.0.0".into()), Some("app@2.0.0".into())),
+                (Some("lib@1.0.0".into()), Some("lib@1.0.0".into())),
+                (None, Some("new@1.0.0".into())),
+                (Some("old@1.0.0".into()), None),
+            ]
+        );
+
+        let (added, removed) = unpaired_members(&members);
+        let names = |packages: Vec<&Package>| {
+            packages
+                .iter()
+                .map(|package| package.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(added), ["new"]);
+        assert_eq!(names(removed), ["old"]);
+    }
+}
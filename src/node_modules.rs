@@ -20,6 +20,18 @@ pub struct NodeModules {
     parent: Option<Weak<NodeModules>>,
 }
 
+/// The name a package is required by: its folder name, prefixed by the scope
+/// folder for scoped packages
+fn install_name(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match path.parent().and_then(Path::file_name) {
+        Some(scope) if scope.to_string_lossy().starts_with('@') => {
+            format!("{}/{}", scope.to_string_lossy(), name)
+        }
+        _ => name.to_string(),
+    }
+}
+
 impl NodeModules {
     pub fn from_folder(folder: PathBuf) -> Result<Rc<Self>> {
         Self::new(folder, None, None)
@@ -61,20 +73,21 @@ impl NodeModules {
                     if let Some(package_data) =
                         PackageJsonData::from_folder_with_id(&scoped_path, id)?
                     {
-                        packages.insert(package_data.name.clone(), Rc::new(package_data));
+                        packages.insert(install_name(&scoped_path), Rc::new(package_data));
                     }
                 }
             } else {
-                // Handle regular packages
+                // Handle regular packages. They are keyed by folder name, which
+                // differs from the package name for aliases (`"foo": "npm:bar@1"`)
                 if let Some(package_data) = PackageJsonData::from_folder_with_id(&path, id)? {
-                    packages.insert(package_data.name.clone(), Rc::new(package_data));
+                    packages.insert(install_name(&path), Rc::new(package_data));
                 }
             }
         }
 
         if let Some(extra_packages) = extra_packages {
             for package in extra_packages {
-                packages.insert(package.name.clone(), Rc::new(package));
+                packages.insert(install_name(&package.install_path), Rc::new(package));
             }
         }
 
@@ -93,9 +106,29 @@ impl NodeModules {
     }
 
     pub fn get_package(&self, name: &str) -> Option<Rc<PackageJsonData>> {
-        self.packages.get(name).map(|p| p.clone()).or_else(|| {
-            self.parent()
-                .and_then(|p| p.get_package(name).map(|p| p.clone()))
-        })
+        self.packages
+            .get(name)
+            .cloned()
+            .or_else(|| self.parent().and_then(|p| p.get_package(name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packages_are_keyed_by_install_name() {
+        // `"foo": "npm:bar@^1.0.0"` installs bar into node_modules/foo
+        let node_modules =
+            NodeModules::from_folder("tests/diff/right/node_modules".into()).unwrap();
+        let alias = node_modules.get_package("foo").unwrap();
+        assert_eq!(alias.name, "bar");
+        assert_eq!(alias.version.as_ref().unwrap().to_string(), "1.0.0");
+        assert!(node_modules.get_package("bar").is_none());
+
+        let node_modules = NodeModules::from_folder("tests/workspace/node_modules".into()).unwrap();
+        assert!(node_modules.get_package("@workspace/foo").is_some());
+        assert!(node_modules.get_package("foo").is_none());
     }
 }
@@ -0,0 +1,165 @@
+use color_eyre::eyre::Result;
+use semver::Version;
+use serde::Serialize;
+
+use crate::{
+    extended_version_req::ExtendedVersionReq,
+    package::{Package, PackageEntry},
+    ptree_impl::sorted_values,
+    registry::Registry,
+    version_bump::Bump,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedDependency {
+    pub name: String,
+    pub required: String,
+    /// Whether the dependency was declared in devDependencies
+    pub dev: bool,
+    /// The installed version, or None if it isn't installed
+    pub current: Option<Version>,
+    /// The highest published version satisfying the declared range
+    pub wanted: Option<Version>,
+    pub latest: Option<Version>,
+    /// The kind of upgrade from the current version to the latest one
+    pub bump: Option<Bump>,
+}
+
+/// Compares the direct dependencies of a package against the versions
+/// published in the registry. Dependencies installed from elsewhere (e.g.
+/// `workspace:` or `file:` ones), ones the registry doesn't know and
+/// up-to-date ones are left out.
+pub fn find_outdated(package: &Package, registry: &Registry) -> Result<Vec<OutdatedDependency>> {
+    let mut outdated = Vec::new();
+
+    let dependencies = sorted_values(&package.dependencies)
+        .into_iter()
+        .map(|d| (d, false))
+        .chain(
+            sorted_values(&package.dev_dependencies)
+                .into_iter()
+                .map(|d| (d, true)),
+        );
+
+    for (dependency, dev) in dependencies {
+        if !is_registry_spec(&dependency.version_req) {
+            continue;
+        }
+
+        // Aliased dependencies (`npm:name@range`) are looked up by their real name
+        let (name, current) = match &dependency.package {
+            PackageEntry::Resolved(key) => (key.name.clone(), key.version.clone()),
+            _ => (dependency.name.clone(), None),
+        };
+        let Some(packument) = registry.packument(&name)? else {
+            continue;
+        };
+
+        let mut versions = packument
+            .versions
+            .keys()
+            .filter_map(|v| Version::parse(v).ok())
+            .collect::<Vec<_>>();
+        versions.sort();
+
+        let wanted = versions
+            .iter()
+            .rev()
+            .find(|v| dependency.version_req.matches(v) == Some(true))
+            .cloned();
+        let latest = packument
+            .dist_tags
+            .get("latest")
+            .and_then(|v| Version::parse(v).ok())
+            .or_else(|| versions.iter().rev().find(|v| v.pre.is_empty()).cloned());
+
+        let bump = current
+            .as_ref()
+            .zip(latest.as_ref())
+            .and_then(|(current, latest)| Bump::between(current, latest))
+            .filter(|bump| *bump != Bump::Downgrade);
+        if current.is_some() && bump.is_none() && wanted <= current {
+            continue;
+        }
+
+        outdated.push(OutdatedDependency {
+            name: dependency.name.clone(),
+            required: dependency.version_req.to_string(),
+            dev,
+            current,
+            wanted,
+            latest,
+            bump,
+        });
+    }
+
+    Ok(outdated)
+}
+
+/// Whether a dependency is installed from the registry: a range, a dist-tag
+/// or an `npm:` alias, rather than a workspace, path, git or tarball URL.
+fn is_registry_spec(version_req: &ExtendedVersionReq) -> bool {
+    match version_req {
+        ExtendedVersionReq::Workspace(_) => false,
+        ExtendedVersionReq::Unchecked(spec) => {
+            spec.starts_with("npm:") || !(spec.contains(':') || spec.contains('/'))
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_find_outdated() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let package = resolver.resolve(Path::new("tests/registry")).unwrap();
+        let registry = Registry::open("tests/registry/packuments").unwrap();
+
+        // @scope/gone and fresh are up to date, and the registry's local and member packages
+        // aren't the ones installed from file: and workspace:
+        let outdated = find_outdated(&package, &registry)
+            .unwrap()
+            .into_iter()
+            .map(|d| {
+                format!(
+                    "{} {} dev={} {:?} -> wanted {:?}, latest {:?} ({:?})",
+                    d.name,
+                    d.required,
+                    d.dev,
+                    d.current.map(|v| v.to_string()),
+                    d.wanted.map(|v| v.to_string()),
+                    d.latest.map(|v| v.to_string()),
+                    d.bump
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outdated,
+            vec![
+                r#"old ^1.0.0 dev=false Some("1.0.0") -> wanted Some("1.2.0"), latest Some("2.1.0") (Some(Major))"#,
+                r#"minor ~1.2.0 dev=true Some("1.2.0") -> wanted Some("1.2.5"), latest Some("1.3.0") (Some(Minor))"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_registry_spec() {
+        assert!(is_registry_spec(&ExtendedVersionReq::parse("^1.0.0")));
+        assert!(is_registry_spec(&ExtendedVersionReq::parse("latest")));
+        assert!(is_registry_spec(&ExtendedVersionReq::parse(
+            "npm:react@^18"
+        )));
+        assert!(!is_registry_spec(&ExtendedVersionReq::parse("workspace:*")));
+        assert!(!is_registry_spec(&ExtendedVersionReq::parse("file:../lib")));
+        assert!(!is_registry_spec(&ExtendedVersionReq::parse("user/repo")));
+        assert!(!is_registry_spec(&ExtendedVersionReq::parse(
+            "https://example.com/pkg.tgz"
+        )));
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use crate::listing::InstalledPackage;
+
+const HEADER: [&str; 8] = [
+    "name",
+    "version",
+    "install_path",
+    "depth",
+    "dependents",
+    "dev_only",
+    "license",
+    "size",
+];
+
+/// Renders one row per installed package copy, separated by commas (quoted
+/// as in RFC 4180) or by tabs
+pub fn render(rows: &[InstalledPackage], separator: char) -> String {
+    let mut out = String::new();
+    write_row(&mut out, &HEADER.map(|h| h.to_string()), separator);
+    for row in rows {
+        write_row(
+            &mut out,
+            &[
+                row.package.name.clone(),
+                row.package.version_str(),
+                row.install_path.display().to_string(),
+                row.depth.to_string(),
+                row.dependents.to_string(),
+                row.dev_only.to_string(),
+                row.license.clone().unwrap_or_default(),
+                row.size.to_string(),
+            ],
+            separator,
+        );
+    }
+    out
+}
+
+fn write_row(out: &mut String, fields: &[String], separator: char) {
+    let fields = fields
+        .iter()
+        .map(|field| escape(field, separator))
+        .collect::<Vec<_>>();
+    let _ = writeln!(out, "{}", fields.join(&separator.to_string()));
+}
+
+fn escape(field: &str, separator: char) -> String {
+    if separator == '\t' {
+        // TSV has no quoting, so characters that would break the row are replaced
+        field.replace(['\t', '\n', '\r'], " ")
+    } else if field.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("MIT", ','), "MIT");
+        assert_eq!(escape("a, \"b\"", ','), "\"a, \"\"b\"\"\"");
+        assert_eq!(escape("a\tb", '\t'), "a b");
+        assert_eq!(escape("a, b", '\t'), "a, b");
+    }
+}
@@ -0,0 +1,231 @@
+use color_eyre::eyre::{Result, eyre};
+use serde_json::{Value, json};
+
+use crate::sbom::{Checksum, Component};
+
+/// Renders a CycloneDX 1.5 JSON document. The first component describes the
+/// document itself; there is no serial number or timestamp so that the same
+/// install always produces the same output.
+pub fn render(components: &[Component]) -> Result<String> {
+    let (root, installed) = components
+        .split_first()
+        .ok_or(eyre!("There are no packages to describe"))?;
+
+    let dependencies = components
+        .iter()
+        .map(|component| {
+            let mut depends_on = Vec::new();
+            for (purl, _) in &component.dependencies {
+                if !depends_on.contains(purl) {
+                    depends_on.push(purl.clone());
+                }
+            }
+            json!({ "ref": component.purl, "dependsOn": depends_on })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::to_string_pretty(&json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "nmvc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": component(root, "application"),
+        },
+        "components": installed
+            .iter()
+            .map(|c| component(c, "library"))
+            .collect::<Vec<_>>(),
+        "dependencies": dependencies,
+    }))?)
+}
+
+fn component(component: &Component, kind: &str) -> Value {
+    let package = &component.package;
+    let (group, name) = match package
+        .name
+        .strip_prefix('@')
+        .and_then(|n| n.split_once('/'))
+    {
+        Some((scope, name)) => (Some(format!("@{}", scope)), name),
+        None => (None, package.name.as_str()),
+    };
+
+    let mut value = json!({
+        "type": kind,
+        "bom-ref": component.purl,
+        "name": name,
+        "purl": component.purl,
+    });
+    if let Some(group) = group {
+        value["group"] = json!(group);
+    }
+    if let Some(version) = &package.version {
+        value["version"] = json!(version.to_string());
+    }
+    // A free-form name for values that aren't SPDX expressions
+    match (component.spdx_license(), &component.license.expression) {
+        (Some(license), _) => {
+            value["licenses"] = json!([{ "expression": license.expression }]);
+        }
+        (None, Some(name)) => value["licenses"] = json!([{ "license": { "name": name } }]),
+        (None, None) => {}
+    }
+    let hashes = component.hashes.iter().filter_map(hash).collect::<Vec<_>>();
+    if !hashes.is_empty() {
+        value["hashes"] = json!(hashes);
+    }
+    if component.dev_only {
+        // Not shipped at runtime
+        value["scope"] = json!("excluded");
+    }
+    value
+}
+
+fn hash(checksum: &Checksum) -> Option<Value> {
+    let algorithm = match checksum.algorithm.as_str() {
+        "sha1" => "SHA-1",
+        "sha256" => "SHA-256",
+        "sha384" => "SHA-384",
+        "sha512" => "SHA-512",
+        _ => return None,
+    };
+    Some(json!({ "alg": algorithm, "content": checksum.hex }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::Path};
+
+    use super::*;
+    use crate::{graph::DependencyGraph, resolver::Resolver, sbom};
+
+    fn render_fixture(path: &str) -> Value {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new(path)).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let production = DependencyGraph::from_roots(&roots, false);
+        let components = sbom::collect_components(&graph, &production);
+        serde_json::from_str(&render(&components).unwrap()).unwrap()
+    }
+
+    fn find<'a>(bom: &'a Value, purl: &str) -> &'a Value {
+        bom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|component| component["purl"] == purl)
+            .unwrap_or_else(|| panic!("{} is missing", purl))
+    }
+
+    #[test]
+    fn test_render() {
+        let bom = render_fixture("tests/sbom");
+        assert_eq!(
+            bom["metadata"]["component"]["bom-ref"],
+            "pkg:npm/sbom-fixture@1.0.0"
+        );
+
+        // The scope is percent-encoded in the purl and split out as the group.
+        // Only devDependencies reach it, and its hash comes from `_integrity`.
+        let tool = find(&bom, "pkg:npm/%40scope/tool@1.0.0");
+        assert_eq!(tool["group"], "@scope");
+        assert_eq!(tool["name"], "tool");
+        assert_eq!(tool["scope"], "excluded");
+        assert_eq!(tool["hashes"][0]["alg"], "SHA-512");
+        assert!(
+            tool["hashes"][0]["content"]
+                .as_str()
+                .unwrap()
+                .starts_with("12a740e6")
+        );
+
+        // custom's hash comes from the hidden lockfile
+        let custom = find(&bom, "pkg:npm/custom@1.0.0");
+        assert_eq!(
+            custom["hashes"],
+            json!([{ "alg": "SHA-1", "content": "2a19b6621b6c758628915209b6d0dbd3cca9d8c4" }])
+        );
+        assert_eq!(
+            custom["licenses"],
+            json!([{ "expression": "LicenseRef-Custom-License" }])
+        );
+        assert!(custom.get("group").is_none());
+
+        // dual is also reached from the dev-only tool, but ships at runtime
+        let dual = find(&bom, "pkg:npm/dual@1.0.0");
+        assert!(dual.get("scope").is_none());
+        assert_eq!(
+            dual["licenses"],
+            json!([{ "expression": "(MIT OR LicenseRef-Acme-1.0)" }])
+        );
+        assert_eq!(
+            find(&bom, "pkg:npm/private@1.0.0")["licenses"],
+            json!([{ "license": { "name": "UNLICENSED" } }])
+        );
+
+        let refs = bom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .chain([&bom["metadata"]["component"]])
+            .map(|component| component["bom-ref"].as_str().unwrap())
+            .collect::<HashSet<_>>();
+        for dependency in bom["dependencies"].as_array().unwrap() {
+            assert!(refs.contains(dependency["ref"].as_str().unwrap()));
+            for depends_on in dependency["dependsOn"].as_array().unwrap() {
+                assert!(
+                    refs.contains(depends_on.as_str().unwrap()),
+                    "{} is not a component",
+                    depends_on
+                );
+            }
+        }
+        let tool_dependencies = bom["dependencies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|dependency| dependency["ref"] == "pkg:npm/%40scope/tool@1.0.0")
+            .unwrap();
+        assert_eq!(
+            tool_dependencies["dependsOn"],
+            json!(["pkg:npm/dual@1.0.0"])
+        );
+    }
+
+    #[test]
+    fn test_render_dedupes_purls() {
+        // @workspace/foo is both a member and installed in the root's
+        // node_modules, which the graph keys as two packages
+        let bom = render_fixture("tests/workspace");
+        let purls = bom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|component| component["purl"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            purls
+                .iter()
+                .filter(|purl| purl.starts_with("pkg:npm/%40workspace/foo"))
+                .count(),
+            1,
+            "{purls:?}"
+        );
+        assert_eq!(
+            purls.len(),
+            purls.iter().collect::<HashSet<_>>().len(),
+            "{purls:?}"
+        );
+        assert_eq!(
+            bom["dependencies"].as_array().unwrap().len(),
+            purls.len() + 1
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Write;
+
+use super::Diagram;
+
+/// Renders the diagram as a Graphviz digraph, e.g. for `dot -Tsvg`
+pub fn render(diagram: &Diagram) -> String {
+    let mut out = String::new();
+    out.push_str("digraph dependencies {\n");
+    out.push_str("    rankdir=LR;\n");
+    out.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+    out.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n");
+
+    for (id, node) in diagram.nodes.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    n{} [label=\"{}\", fillcolor=\"{}\"];",
+            id,
+            escape(&node.label),
+            node.status.color()
+        );
+    }
+    for edge in &diagram.edges {
+        let style = if edge.dev { ", style=dashed" } else { "" };
+        let _ = writeln!(
+            out,
+            "    n{} -> n{} [label=\"{}\"{}];",
+            edge.from,
+            edge.to,
+            escape(&edge.label),
+            style
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{Edge, Node, NodeStatus};
+
+    #[test]
+    fn test_render() {
+        let diagram = Diagram {
+            nodes: vec![
+                Node {
+                    label: "app@1.0.0".to_string(),
+                    status: NodeStatus::Satisfied,
+                },
+                Node {
+                    label: "left-pad [MISSING]".to_string(),
+                    status: NodeStatus::Missing,
+                },
+            ],
+            edges: vec![Edge {
+                from: 0,
+                to: 1,
+                label: "^1.0.0".to_string(),
+                dev: true,
+            }],
+            ..Default::default()
+        };
+
+        let dot = render(&diagram);
+        assert!(dot.contains("    n0 [label=\"app@1.0.0\", fillcolor=\"#d4edda\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [label=\"^1.0.0\", style=dashed];\n"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    rc::Rc,
+};
+
+use color_eyre::eyre::Result;
+use serde_json::{Value, json};
+
+use crate::{
+    graph::DependencyGraph,
+    package::{Dependency, Package, PackageEntry, PackageKey},
+    ptree_impl::sorted_values,
+};
+
+const TEMPLATE: &str = include_str!("report.html");
+
+/// Renders a single static page with a collapsible tree for each root, a
+/// search box and a detail panel per installed package
+pub fn render(roots: &[Rc<Package>]) -> Result<String> {
+    let graph = DependencyGraph::from_roots(roots, true);
+
+    // A folder reached through different resolvers, e.g. a workspace member
+    // and its node_modules symlink, is listed once under all of its keys
+    let mut installed_packages: Vec<(&Rc<Package>, Vec<PackageKey>)> = Vec::new();
+    let mut paths = HashMap::new();
+    let mut ids = HashMap::new();
+    for package in graph.packages() {
+        let id = *paths.entry(&package.data.install_path).or_insert_with(|| {
+            installed_packages.push((package, Vec::new()));
+            installed_packages.len() - 1
+        });
+        let key = PackageKey::from(package.as_ref());
+        installed_packages[id].1.push(key.clone());
+        ids.insert(key, id);
+    }
+
+    let packages = installed_packages
+        .iter()
+        .map(|(package, keys)| {
+            let dependents = keys
+                .iter()
+                .flat_map(|key| graph.dependents(key))
+                .map(|dependent| ids[dependent])
+                .collect::<BTreeSet<_>>();
+            let checks = dependencies(package)
+                .map(|(dependency, dev)| {
+                    json!({
+                        "name": dependency.name,
+                        "required": dependency.version_req.to_string(),
+                        "package": resolved_id(&dependency.package, &ids),
+                        "installed": installed(&dependency.package),
+                        "status": status(&dependency),
+                        "dev": dev,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            json!({
+                "name": package.name,
+                "version": package.version.as_ref().map(|v| v.to_string()),
+                "path": package.data.install_path,
+                "dependents": dependents,
+                "checks": checks,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Each root is deduplicated on its own, like the text tree
+    let mut trees = Vec::new();
+    for root in roots {
+        if let Some(resolver) = root.resolver() {
+            resolver.refresh_visited();
+        }
+        trees.push(tree_node(&ids, root.name.clone(), None, Some(root.clone())));
+    }
+
+    let title = roots
+        .iter()
+        .map(|root| PackageKey::from(root.as_ref()).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let data = serde_json::to_string(&json!({ "roots": trees, "packages": packages }))?
+        // Keep the JSON from closing the script element it is embedded in
+        .replace("</", "<\\/");
+
+    Ok(TEMPLATE
+        .replace("{{TITLE}}", &escape(&title))
+        .replace("{{DATA}}", &data))
+}
+
+/// A tree node for a package reached through `dependency`. This uses the
+/// same `visited` flags as the text tree, so a package's dependencies are
+/// only listed the first time it is reached and later occurrences are
+/// marked as deduped.
+fn tree_node(
+    ids: &HashMap<PackageKey, usize>,
+    name: String,
+    dependency: Option<(&Dependency, bool)>,
+    package: Option<Rc<Package>>,
+) -> Value {
+    let key = package
+        .as_ref()
+        .map(|package| PackageKey::from(package.as_ref()));
+    let deduped = package.as_ref().is_some_and(|p| *p.visited.borrow());
+
+    let mut children = Vec::new();
+    if let Some(package) = &package
+        && !deduped
+    {
+        *package.visited.borrow_mut() = true;
+        let resolver = package.resolver();
+        for (child, dev) in dependencies(package) {
+            let child_package = match &child.package {
+                PackageEntry::Resolved(key) => resolver.as_ref().and_then(|r| r.get_package(key)),
+                _ => None,
+            };
+            children.push(tree_node(
+                ids,
+                child.name.clone(),
+                Some((&child, dev)),
+                child_package,
+            ));
+        }
+    }
+
+    json!({
+        "name": name,
+        "required": dependency.map(|(d, _)| d.version_req.to_string()),
+        "package": key.and_then(|key| ids.get(&key).copied()),
+        "installed": dependency.map(|(d, _)| installed(&d.package)),
+        "status": dependency.map_or("satisfied", |(d, _)| status(d)),
+        "dev": dependency.is_some_and(|(_, dev)| dev),
+        "deduped": deduped,
+        "children": children,
+    })
+}
+
+/// The package's dependencies followed by its dev dependencies, each sorted by name
+fn dependencies(package: &Package) -> impl Iterator<Item = (Dependency, bool)> {
+    sorted_values(&package.dependencies)
+        .into_iter()
+        .map(|d| (d, false))
+        .chain(
+            sorted_values(&package.dev_dependencies)
+                .into_iter()
+                .map(|d| (d, true)),
+        )
+}
+
+fn resolved_id(entry: &PackageEntry, ids: &HashMap<PackageKey, usize>) -> Option<usize> {
+    match entry {
+        PackageEntry::Resolved(key) => ids.get(key).copied(),
+        _ => None,
+    }
+}
+
+fn installed(entry: &PackageEntry) -> String {
+    match entry {
+        PackageEntry::Resolved(key) => key.version_str(),
+        PackageEntry::Missing => "[MISSING]".to_string(),
+        PackageEntry::Truncated => "[TRUNCATED]".to_string(),
+    }
+}
+
+/// Whether the installed package meets the declared range
+fn status(dependency: &Dependency) -> &'static str {
+    match &dependency.package {
+        PackageEntry::Missing => "missing",
+        PackageEntry::Truncated => "truncated",
+        PackageEntry::Resolved(_) => match dependency.package.satisfies(&dependency.version_req) {
+            Some(true) => "satisfied",
+            Some(false) => "mismatched",
+            // e.g. workspace: or file: ranges
+            None => "unchecked",
+        },
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    fn render_fixture(path: &str) -> Value {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new(path)).unwrap();
+        let html = render(&roots).unwrap();
+        let start = html.find("id=\"data\">").unwrap() + "id=\"data\">".len();
+        let end = start + html[start..].find("</script>").unwrap();
+        serde_json::from_str(&html[start..end].replace("<\\/", "</")).unwrap()
+    }
+
+    #[test]
+    fn test_render_workspace() {
+        let data = render_fixture("tests/workspace");
+
+        // The member @workspace/foo and my-app's dependency on it are one package
+        let packages = data["packages"].as_array().unwrap();
+        let names = packages
+            .iter()
+            .map(|package| package["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["test-workspace-root", "@workspace/foo", "my-app"]);
+        assert_eq!(packages[1]["dependents"], json!([2]));
+        assert_eq!(packages[2]["checks"][0]["package"], 1);
+        assert_eq!(data["roots"][2]["children"][0]["package"], 1);
+    }
+
+    #[test]
+    fn test_render_dedupes_repeated_subtrees() {
+        let data = render_fixture("tests/cycles");
+        let a = &data["roots"][1];
+        assert_eq!(a["name"], "a");
+
+        // x is listed below b > a first, then deduped as a's own dependency
+        let x = &a["children"][1];
+        assert_eq!(x["name"], "x");
+        assert_eq!(x["deduped"], true);
+        assert_eq!(x["children"], json!([]));
+
+        let packages = data["packages"].as_array().unwrap();
+        let x = &packages[x["package"].as_u64().unwrap() as usize];
+        assert_eq!(x["dependents"].as_array().unwrap().len(), 2);
+    }
+}
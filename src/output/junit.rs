@@ -0,0 +1,179 @@
+use std::{collections::HashSet, fmt::Write, rc::Rc};
+
+use crate::{
+    check::{Finding, Problem},
+    package::{Package, PackageKey},
+    ptree_impl::sorted_values,
+};
+
+struct TestCase {
+    name: String,
+    classname: String,
+    failure: Option<String>,
+}
+
+struct TestSuite {
+    name: String,
+    cases: Vec<TestCase>,
+}
+
+/// Renders the findings as JUnit XML. Each first-party package (the root and
+/// workspace members) is a testsuite with a testcase per declared dependency,
+/// which fails when the dependency is missing or out of range. Problems found
+/// elsewhere, such as in installed packages, go in a last testsuite so that
+/// the report fails whenever `check` does.
+pub fn render(members: &[Rc<Package>], findings: &[Finding]) -> String {
+    let mut reported = HashSet::new();
+    let mut suites = Vec::new();
+
+    for member in members {
+        let key = PackageKey::from(member.as_ref());
+        let mut cases = Vec::new();
+        for (dependencies, section, dev) in [
+            (&member.dependencies, "dependencies", false),
+            (&member.dev_dependencies, "devDependencies", true),
+        ] {
+            for dependency in sorted_values(dependencies) {
+                let failure = findings.iter().enumerate().find(|(_, finding)| {
+                    finding.package == key
+                        && finding.dev == dev
+                        && match &finding.problem {
+                            Problem::Mismatch { dependency: d, .. }
+                            | Problem::Missing { dependency: d, .. } => *d == dependency.name,
+                            _ => false,
+                        }
+                });
+                cases.push(TestCase {
+                    name: format!("{}@{}", dependency.name, dependency.version_req),
+                    classname: format!("{}.{}", member.name, section),
+                    failure: failure.map(|(index, finding)| {
+                        reported.insert(index);
+                        finding.message()
+                    }),
+                });
+            }
+        }
+        suites.push(TestSuite {
+            name: key.to_string(),
+            cases,
+        });
+    }
+
+    let others = findings
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !reported.contains(index))
+        .map(|(_, finding)| TestCase {
+            name: finding.json_path().join("."),
+            classname: finding.package.to_string(),
+            failure: Some(finding.message()),
+        })
+        .collect::<Vec<_>>();
+    if !others.is_empty() {
+        suites.push(TestSuite {
+            name: "installed packages".to_string(),
+            cases: others,
+        });
+    }
+
+    let tests = suites.iter().map(|s| s.cases.len()).sum::<usize>();
+    let failures = suites.iter().map(failure_count).sum::<usize>();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"nmvc check\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    );
+    for suite in &suites {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">",
+            escape(&suite.name),
+            suite.cases.len(),
+            failure_count(suite)
+        );
+        for case in &suite.cases {
+            let attributes = format!(
+                "name=\"{}\" classname=\"{}\"",
+                escape(&case.name),
+                escape(&case.classname)
+            );
+            match &case.failure {
+                Some(message) => {
+                    let _ = writeln!(out, "    <testcase {}>", attributes);
+                    let _ = writeln!(
+                        out,
+                        "      <failure message=\"{}\">{}</failure>",
+                        escape(message),
+                        escape(message)
+                    );
+                    let _ = writeln!(out, "    </testcase>");
+                }
+                None => {
+                    let _ = writeln!(out, "    <testcase {}/>", attributes);
+                }
+            }
+        }
+        let _ = writeln!(out, "  </testsuite>");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn failure_count(suite: &TestSuite) -> usize {
+    suite.cases.iter().filter(|c| c.failure.is_some()).count()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{check, graph::DependencyGraph, resolver::Resolver};
+
+    #[test]
+    fn test_render() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/check")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let mut findings = check::check_dependencies(&graph);
+        findings.extend(check::check_peers(&graph));
+
+        // The unmet peer is only reported in the last testsuite, and the
+        // vendored tarball's path needs escaping
+        assert_eq!(
+            render(&roots, &findings),
+            "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites name=\"nmvc check\" tests=\"6\" failures=\"4\">
+  <testsuite name=\"check-fixture@1.0.0\" tests=\"5\" failures=\"3\" errors=\"0\" skipped=\"0\">
+    <testcase name=\"host@^1.0.0\" classname=\"check-fixture.dependencies\"/>
+    <testcase name=\"missing@^1.0.0\" classname=\"check-fixture.dependencies\">
+      <failure message=\"check-fixture@1.0.0 requires dependency missing@^1.0.0, but it is not installed\">check-fixture@1.0.0 requires dependency missing@^1.0.0, but it is not installed</failure>
+    </testcase>
+    <testcase name=\"plugin@^1.0.0\" classname=\"check-fixture.dependencies\"/>
+    <testcase name=\"range@&lt;1.0.0\" classname=\"check-fixture.dependencies\">
+      <failure message=\"check-fixture@1.0.0 requires dependency range@&lt;1.0.0, but 1.0.0 is installed\">check-fixture@1.0.0 requires dependency range@&lt;1.0.0, but 1.0.0 is installed</failure>
+    </testcase>
+    <testcase name=\"vendored@file:vendor/a&amp;b &quot;c&quot;.tgz\" classname=\"check-fixture.dependencies\">
+      <failure message=\"check-fixture@1.0.0 requires dependency vendored@file:vendor/a&amp;b &quot;c&quot;.tgz, but it is not installed\">check-fixture@1.0.0 requires dependency vendored@file:vendor/a&amp;b &quot;c&quot;.tgz, but it is not installed</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name=\"installed packages\" tests=\"1\" failures=\"1\" errors=\"0\" skipped=\"0\">
+    <testcase name=\"peerDependencies.host\" classname=\"plugin@1.0.0\">
+      <failure message=\"plugin@1.0.0 requires peer dependency host@^2.0.0, but 1.0.0 is installed\">plugin@1.0.0 requires peer dependency host@^2.0.0, but 1.0.0 is installed</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write,
+    rc::Rc,
+};
+
+use crate::{
+    diff::{
+        ChangeKind, ChangedPackageEntry, ChangedPackageKey, DiffFilter, DiffSummary,
+        DiffedDependency, DiffedPackage, DiffedPackageAndVersionReq, Differ, SummaryCollector,
+    },
+    package::{Package, PackageKey},
+    ptree_impl::sorted_values,
+    version_bump::Bump,
+};
+
+use super::{changed_label, entry_version, pair_label};
+
+/// The changes found below one direct dependency of the root
+struct Group {
+    name: String,
+    changes: BTreeSet<String>,
+    /// The packages walked below the direct dependency
+    seen: HashSet<ChangedPackageKey>,
+}
+
+/// Renders the diffs as a report for pull request comments: a summary table,
+/// then one collapsible section each for the direct dependency changes, the
+/// transitive changes grouped by the direct dependency that pulled them in,
+/// and the mismatches the right side introduced
+pub fn render(
+    differs: &[Rc<Differ>],
+    added_members: &[&Package],
+    removed_members: &[&Package],
+) -> String {
+    // The summary counts the changes the sections below list
+    let mut sections = String::new();
+    let mut summaries = Vec::new();
+    for differ in differs {
+        if differs.len() > 1 {
+            let _ = writeln!(
+                sections,
+                "### {}\n",
+                code(&changed_label(&differ.root_key()))
+            );
+        }
+        let mut collector = SummaryCollector::default();
+        render_diff(&mut sections, differ, &mut collector);
+        summaries.push(collector.finish());
+    }
+
+    let mut out = String::new();
+    out.push_str("## Dependency changes\n\n");
+    render_summary(
+        &mut out,
+        &differs[0].filter,
+        &summaries,
+        added_members.len(),
+        removed_members.len(),
+    );
+    out.push_str(&sections);
+
+    if !added_members.is_empty() || !removed_members.is_empty() {
+        out.push_str("### Workspace members\n\n");
+        for package in added_members {
+            let _ = writeln!(
+                out,
+                "- {} added",
+                code(&PackageKey::from(*package).to_string())
+            );
+        }
+        for package in removed_members {
+            let _ = writeln!(
+                out,
+                "- {} removed",
+                code(&PackageKey::from(*package).to_string())
+            );
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Counts the changes of the diffs by kind. Version changes count once across
+/// the diffs, the other kinds once per diff.
+fn render_summary(
+    out: &mut String,
+    filter: &DiffFilter,
+    summaries: &[DiffSummary],
+    added_members: usize,
+    removed_members: usize,
+) {
+    let mut counts = HashMap::new();
+    let changes = summaries
+        .iter()
+        .flat_map(|summary| &summary.changes)
+        .collect::<HashSet<_>>();
+    for bump in changes.iter().filter_map(|change| change.bump()) {
+        *counts.entry(bump).or_insert(0) += 1;
+    }
+    let summary = summaries
+        .iter()
+        .fold(DiffSummary::default(), |total, summary| DiffSummary {
+            added: total.added + summary.added,
+            removed: total.removed + summary.removed,
+            renamed: total.renamed + summary.renamed,
+            became_missing: total.became_missing + summary.became_missing,
+            became_resolved: total.became_resolved + summary.became_resolved,
+            requirement_only: total.requirement_only + summary.requirement_only,
+            ..total
+        });
+
+    let mut rows = Bump::ALL
+        .into_iter()
+        .filter(|bump| filter.matches_bump(Some(*bump)))
+        .map(|bump| (bump.as_str(), counts.get(&bump).copied().unwrap_or(0)))
+        .collect::<Vec<_>>();
+    for (kind, count) in [
+        (ChangeKind::BecameMissing, summary.became_missing),
+        (ChangeKind::BecameResolved, summary.became_resolved),
+    ] {
+        if filter.matches(Some(kind)) {
+            rows.push((kind.as_str(), count));
+        }
+    }
+    if filter.only.is_empty() {
+        rows.extend([
+            ("added", summary.added),
+            ("removed", summary.removed),
+            ("renamed", summary.renamed),
+            ("requirement only", summary.requirement_only),
+            ("added members", added_members),
+            ("removed members", removed_members),
+        ]);
+    }
+
+    out.push_str("| Change | Count |\n| --- | ---: |\n");
+    for (label, count) in rows.iter().filter(|(_, count)| *count > 0) {
+        let _ = writeln!(out, "| {} | {} |", label, count);
+    }
+    if rows.iter().all(|(_, count)| *count == 0) {
+        out.push_str("| none | 0 |\n");
+    }
+    out.push('\n');
+}
+
+fn render_diff(out: &mut String, differ: &Differ, collector: &mut SummaryCollector) {
+    let filter = &differ.filter;
+    let root_key = differ.root_key();
+    let Some(root) = differ.get_package(&root_key) else {
+        out.push_str("No changes.\n\n");
+        return;
+    };
+
+    let mut direct = Vec::new();
+    let mut groups = Vec::new();
+    let mut mismatches = BTreeSet::new();
+
+    let mut dependencies = Vec::new();
+    if filter.shows_dependencies(0, false) {
+        dependencies.extend(
+            sorted_values(&root.dependencies)
+                .into_iter()
+                .map(|d| (d, false)),
+        );
+    }
+    if filter.shows_dependencies(0, true) {
+        dependencies.extend(
+            sorted_values(&root.dev_dependencies)
+                .into_iter()
+                .map(|d| (d, true)),
+        );
+    }
+    for (dependency, dev) in dependencies {
+        if filter.excludes(&dependency) {
+            continue;
+        }
+        if filter.includes(&dependency) {
+            collector.add(&dependency);
+            direct.extend(direct_row(&dependency, dev, filter));
+            if dependency.introduces_mismatch() {
+                mismatches.insert(mismatch_row(&root_key, &dependency));
+            }
+        }
+
+        if let DiffedPackageAndVersionReq::Changed {
+            package: ChangedPackageEntry::Resolved(key),
+            ..
+        } = &dependency.package
+            && let Some(package) = differ.get_package(key)
+        {
+            let mut group = Group {
+                name: dependency.name.clone(),
+                changes: BTreeSet::new(),
+                seen: HashSet::from([key.clone()]),
+            };
+            collect_transitive(
+                differ,
+                key,
+                &package,
+                2,
+                &mut group,
+                &mut mismatches,
+                collector,
+            );
+            if !group.changes.is_empty() {
+                groups.push(group);
+            }
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "<details open>\n<summary>Direct dependencies ({})</summary>\n",
+        direct.len()
+    );
+    if direct.is_empty() {
+        out.push_str("No changes.\n");
+    } else {
+        out.push_str("| Package | Requirement | Version | Change |\n| --- | --- | --- | --- |\n");
+        for row in &direct {
+            out.push_str(row);
+            out.push('\n');
+        }
+    }
+    out.push_str("\n</details>\n\n");
+
+    let _ = writeln!(
+        out,
+        "<details>\n<summary>Transitive dependencies ({})</summary>\n",
+        groups.iter().map(|g| g.changes.len()).sum::<usize>()
+    );
+    if groups.is_empty() {
+        out.push_str("No changes.\n\n");
+    }
+    for group in &groups {
+        let _ = writeln!(
+            out,
+            "<details>\n<summary>via {} ({})</summary>\n",
+            code(&group.name),
+            group.changes.len()
+        );
+        for change in &group.changes {
+            let _ = writeln!(out, "- {}", change);
+        }
+        out.push_str("\n</details>\n\n");
+    }
+    out.push_str("</details>\n\n");
+
+    let _ = writeln!(
+        out,
+        "<details{}>\n<summary>New mismatches ({})</summary>\n",
+        if mismatches.is_empty() { "" } else { " open" },
+        mismatches.len()
+    );
+    if mismatches.is_empty() {
+        out.push_str("None.\n");
+    } else {
+        out.push_str(
+            "| Dependent | Dependency | Requirement | Installed |\n| --- | --- | --- | --- |\n",
+        );
+        for row in &mismatches {
+            out.push_str(row);
+            out.push('\n');
+        }
+    }
+    out.push_str("\n</details>\n\n");
+}
+
+/// Walks the subtree below a direct dependency, collecting the changes that
+/// pass the filter and the mismatches introduced
+fn collect_transitive(
+    differ: &Differ,
+    key: &ChangedPackageKey,
+    package: &DiffedPackage,
+    depth: usize,
+    group: &mut Group,
+    mismatches: &mut BTreeSet<String>,
+    collector: &mut SummaryCollector,
+) {
+    let filter = &differ.filter;
+    if filter.max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return;
+    }
+
+    let dependencies = sorted_values(&package.dependencies)
+        .into_iter()
+        .chain(sorted_values(&package.dev_dependencies));
+    for dependency in dependencies {
+        if filter.excludes(&dependency) {
+            continue;
+        }
+        if filter.includes(&dependency) {
+            collector.add(&dependency);
+            group.changes.extend(change_line(&dependency, filter));
+            if dependency.introduces_mismatch() {
+                mismatches.insert(mismatch_row(key, &dependency));
+            }
+        }
+
+        if let DiffedPackageAndVersionReq::Changed {
+            package: ChangedPackageEntry::Resolved(child),
+            ..
+        } = &dependency.package
+            && group.seen.insert(child.clone())
+            && let Some(child_package) = differ.get_package(child)
+        {
+            collect_transitive(
+                differ,
+                child,
+                &child_package,
+                depth + 1,
+                group,
+                mismatches,
+                collector,
+            );
+        }
+    }
+}
+
+/// A table row for a changed direct dependency
+fn direct_row(dependency: &DiffedDependency, dev: bool, filter: &DiffFilter) -> Option<String> {
+    let (requirement, version, change) = match &dependency.package {
+        DiffedPackageAndVersionReq::Changed {
+            package,
+            version_req_left,
+            version_req_right,
+        } => {
+            let requirement = if version_req_left == version_req_right {
+                code(&version_req_left.to_string())
+            } else {
+                format!(
+                    "{} -> {}",
+                    code(&version_req_left.to_string()),
+                    code(&version_req_right.to_string())
+                )
+            };
+            let change = match package {
+                ChangedPackageEntry::Resolved(key) if key.left.version != key.right.version => {
+                    let bump = key.bump();
+                    filter
+                        .matches_bump(bump)
+                        .then(|| bump.map_or("changed", |bump| bump.as_str()))
+                }
+                ChangedPackageEntry::MismatchedResolution { .. } => package
+                    .kind()
+                    .filter(|kind| filter.matches(Some(*kind)))
+                    .map(|kind| kind.as_str()),
+                _ if !filter.only.is_empty() => None,
+                _ if dependency.renamed_from.is_some() => Some("renamed"),
+                _ if version_req_left != version_req_right => Some("requirement"),
+                _ => None,
+            }?;
+            let (left, right) = entry_versions(package);
+            (requirement, pair_label(&left, &right), change)
+        }
+        DiffedPackageAndVersionReq::Added {
+            package,
+            version_req,
+        } if filter.only.is_empty() => (
+            code(&version_req.to_string()),
+            format!("- -> {}", entry_version(package)),
+            "added",
+        ),
+        DiffedPackageAndVersionReq::Removed {
+            package,
+            version_req,
+        } if filter.only.is_empty() => (
+            code(&version_req.to_string()),
+            format!("{} -> -", entry_version(package)),
+            "removed",
+        ),
+        _ => return None,
+    };
+
+    let name = match &dependency.renamed_from {
+        Some(renamed_from) => format!("{} -> {}", code(renamed_from), code(&dependency.name)),
+        None => code(&dependency.name),
+    };
+    let dev = if dev { " (dev)" } else { "" };
+    Some(format!(
+        "| {}{} | {} | {} | {} |",
+        name, dev, requirement, version, change
+    ))
+}
+
+/// A list item for a changed transitive dependency
+fn change_line(dependency: &DiffedDependency, filter: &DiffFilter) -> Option<String> {
+    match &dependency.package {
+        DiffedPackageAndVersionReq::Changed {
+            package: ChangedPackageEntry::Resolved(key),
+            ..
+        } => {
+            if key.left.version != key.right.version && filter.matches_bump(key.bump()) {
+                let bump = key.bump().map_or("changed", |bump| bump.as_str());
+                Some(format!(
+                    "{}: {} -> {} ({})",
+                    code(&key.right.name),
+                    key.left.version_str(),
+                    key.right.version_str(),
+                    bump
+                ))
+            } else if let Some(renamed_from) = &dependency.renamed_from
+                && filter.only.is_empty()
+            {
+                Some(format!(
+                    "{} -> {} (renamed)",
+                    code(renamed_from),
+                    code(&dependency.name)
+                ))
+            } else {
+                None
+            }
+        }
+        DiffedPackageAndVersionReq::Changed { package, .. } => {
+            let kind = package.kind().filter(|kind| filter.matches(Some(*kind)))?;
+            let (left, right) = entry_versions(package);
+            Some(format!(
+                "{}: {} -> {} ({})",
+                code(&dependency.name),
+                left,
+                right,
+                kind.as_str()
+            ))
+        }
+        DiffedPackageAndVersionReq::Added { package, .. } if filter.only.is_empty() => {
+            Some(format!(
+                "{}@{} (added)",
+                code(&dependency.name),
+                entry_version(package)
+            ))
+        }
+        DiffedPackageAndVersionReq::Removed { package, .. } if filter.only.is_empty() => {
+            Some(format!(
+                "{}@{} (removed)",
+                code(&dependency.name),
+                entry_version(package)
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn mismatch_row(dependent: &ChangedPackageKey, dependency: &DiffedDependency) -> String {
+    let (requirement, installed) = match &dependency.package {
+        DiffedPackageAndVersionReq::Changed {
+            package,
+            version_req_right,
+            ..
+        } => (version_req_right, entry_versions(package).1),
+        DiffedPackageAndVersionReq::Added {
+            package,
+            version_req,
+        }
+        | DiffedPackageAndVersionReq::Removed {
+            package,
+            version_req,
+        } => (version_req, entry_version(package)),
+    };
+    format!(
+        "| {} | {} | {} | {} |",
+        code(&dependent.right.to_string()),
+        code(&dependency.name),
+        code(&requirement.to_string()),
+        installed
+    )
+}
+
+/// The installed versions on both sides, or a marker such as `[MISSING]`
+fn entry_versions(entry: &ChangedPackageEntry) -> (String, String) {
+    match entry {
+        ChangedPackageEntry::Resolved(key) => (key.left.version_str(), key.right.version_str()),
+        ChangedPackageEntry::Missing => ("[MISSING]".to_string(), "[MISSING]".to_string()),
+        ChangedPackageEntry::Truncated => ("[TRUNCATED]".to_string(), "[TRUNCATED]".to_string()),
+        ChangedPackageEntry::MismatchedResolution { left, right } => {
+            (entry_version(left), entry_version(right))
+        }
+    }
+}
+
+/// An inline code span that is safe inside table cells
+fn code(s: &str) -> String {
+    format!("`{}`", s.replace('|', "\\|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{diff::tests::diff_fixture, resolver::Resolver};
+
+    fn member(path: &str, name: &str) -> Rc<Package> {
+        let mut resolver = Resolver::new(usize::MAX);
+        crate::resolve_with_members(&mut resolver, Path::new(path))
+            .unwrap()
+            .into_iter()
+            .find(|package| package.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let differs = diff_fixture(DiffFilter::default());
+        let new = member("tests/diff/right", "new");
+        let old = member("tests/diff/left", "old");
+        let markdown = render(&differs, &[new.as_ref()], &[old.as_ref()]);
+
+        assert!(
+            markdown.starts_with(
+                "\
+## Dependency changes
+
+| Change | Count |
+| --- | ---: |
+| major | 2 |
+| minor | 2 |
+| patch | 2 |
+| prerelease | 1 |
+| downgrade | 1 |
+| became missing | 1 |
+| became resolved | 1 |
+| added | 1 |
+| removed | 1 |
+| renamed | 1 |
+| added members | 1 |
+| removed members | 1 |
+
+### `diff-fixture@1.0.0`
+"
+            ),
+            "{markdown}"
+        );
+        assert!(
+            markdown
+                .contains("| `bar` -> `foo` | `^1.0.0` -> `npm:bar@^1.0.0` | 1.0.0 | renamed |\n")
+        );
+        assert!(markdown.contains("| `gone` | `^1.0.0` | 1.0.0 -> [MISSING] | became missing |\n"));
+        assert!(markdown.contains("| `tool` (dev) | `^1.0.0` | 1.0.0 -> 1.1.0 | minor |\n"));
+        assert!(
+            markdown.contains(
+                "<summary>via `major` (1)</summary>\n\n- `deep`: 1.0.0 -> 1.0.1 (patch)\n"
+            )
+        );
+        assert!(markdown.contains("| `diff-fixture@1.0.0` | `gone` | `^1.0.0` | [MISSING] |\n"));
+        assert!(
+            markdown.ends_with(
+                "### Workspace members\n\n- `new@1.0.0` added\n- `old@1.0.0` removed\n\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_dependency_type_filters() {
+        let dev = DiffFilter {
+            dev: true,
+            ..Default::default()
+        };
+        let markdown = render(&diff_fixture(dev)[..1], &[], &[]);
+        assert!(
+            markdown.contains("<summary>Direct dependencies (1)</summary>"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("| `tool` (dev) |"), "{markdown}");
+        assert!(
+            markdown.contains("<summary>Transitive dependencies (0)</summary>"),
+            "{markdown}"
+        );
+
+        let prod = DiffFilter {
+            prod: true,
+            ..Default::default()
+        };
+        let markdown = render(&diff_fixture(prod)[..1], &[], &[]);
+        assert!(
+            markdown.contains("<summary>Direct dependencies (9)</summary>"),
+            "{markdown}"
+        );
+        assert!(!markdown.contains("`tool`"), "{markdown}");
+    }
+
+    #[test]
+    fn test_render_summary_filters() {
+        // The table counts the same changes as the sections
+        let dev = DiffFilter {
+            dev: true,
+            ..Default::default()
+        };
+        let markdown = render(&diff_fixture(dev), &[], &[]);
+        assert!(
+            markdown.starts_with(
+                "## Dependency changes\n\n| Change | Count |\n| --- | ---: |\n| minor | 1 |\n\n"
+            ),
+            "{markdown}"
+        );
+
+        let patterns = ["major", "gone", "late", "bar", "app", "lib"].map(String::from);
+        let exclude = DiffFilter {
+            exclude: Some(crate::source_scan::build_globset(&patterns).unwrap()),
+            ..Default::default()
+        };
+        let markdown = render(&diff_fixture(exclude)[..1], &[], &[]);
+        assert!(
+            markdown.starts_with(
+                "\
+## Dependency changes
+
+| Change | Count |
+| --- | ---: |
+| minor | 2 |
+| patch | 1 |
+| prerelease | 1 |
+| downgrade | 1 |
+
+"
+            ),
+            "{markdown}"
+        );
+    }
+
+    #[test]
+    fn test_render_without_changes() {
+        let only = DiffFilter {
+            only: vec![ChangeKind::Major],
+            ..Default::default()
+        };
+        // lib has no major changes
+        let differs = diff_fixture(only);
+        let markdown = render(&differs[2..], &[], &[]);
+        assert!(
+            markdown.starts_with(
+                "## Dependency changes\n\n| Change | Count |\n| --- | ---: |\n| none | 0 |\n"
+            ),
+            "{markdown}"
+        );
+    }
+}
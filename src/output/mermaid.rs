@@ -0,0 +1,67 @@
+use std::fmt::Write;
+
+use super::{Diagram, NodeStatus};
+
+const STATUSES: [NodeStatus; 8] = [
+    NodeStatus::Satisfied,
+    NodeStatus::Mismatched,
+    NodeStatus::Missing,
+    NodeStatus::Duplicated,
+    NodeStatus::Added,
+    NodeStatus::Removed,
+    NodeStatus::Changed,
+    NodeStatus::Unchanged,
+];
+
+/// Renders the diagram as a fenced `graph TD` block that GitHub and GitLab
+/// render inline in comments
+pub fn render(diagram: &Diagram) -> String {
+    let mut out = String::new();
+    out.push_str("```mermaid\ngraph TD\n");
+
+    for (id, node) in diagram.nodes.iter().enumerate() {
+        let _ = writeln!(out, "    n{}[\"{}\"]", id, escape(&node.label));
+    }
+    if diagram.omitted > 0 {
+        let _ = writeln!(
+            out,
+            "    omitted[\"... {} more packages\"]",
+            diagram.omitted
+        );
+    }
+    for edge in &diagram.edges {
+        let arrow = if edge.dev { "-.->" } else { "-->" };
+        let _ = writeln!(
+            out,
+            "    n{} {}|\"{}\"| n{}",
+            edge.from,
+            arrow,
+            escape(&edge.label),
+            edge.to
+        );
+    }
+
+    for status in STATUSES {
+        let ids = diagram
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.status == status)
+            .map(|(id, _)| format!("n{}", id))
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            continue;
+        }
+        let class = format!("{:?}", status).to_lowercase();
+        let _ = writeln!(out, "    classDef {} fill:{}", class, status.color());
+        let _ = writeln!(out, "    class {} {}", ids.join(","), class);
+    }
+
+    out.push_str("```\n");
+    out
+}
+
+/// Mermaid labels can't contain double quotes, even escaped ones
+fn escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
@@ -0,0 +1,614 @@
+pub mod csv;
+pub mod cyclonedx;
+pub mod dot;
+pub mod html;
+pub mod junit;
+pub mod markdown;
+pub mod mermaid;
+pub mod npm_ls;
+pub mod sarif;
+pub mod spdx;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    diff::{ChangedPackageEntry, ChangedPackageKey, DiffedPackageAndVersionReq, Differ},
+    graph::DependencyGraph,
+    package::{Package, PackageEntry, PackageKey},
+    ptree_impl::sorted_values,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TreeFormat {
+    Text,
+    Dot,
+    NpmJson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Text,
+    Dot,
+    Mermaid,
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CheckFormat {
+    Text,
+    Sarif,
+    Junit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    Csv,
+    Tsv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SbomFormat {
+    Cyclonedx,
+    SpdxJson,
+    SpdxTagValue,
+}
+
+/// What a node represents, which decides how it is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    /// Installed and satisfying every range that points at it
+    Satisfied,
+    /// Installed but outside a range that points at it
+    Mismatched,
+    Missing,
+    /// Installed alongside other copies of the same package
+    Duplicated,
+    Added,
+    Removed,
+    Changed,
+    /// Unchanged itself, but on the path to a change
+    Unchanged,
+}
+
+impl NodeStatus {
+    /// The fill color of the node
+    pub fn color(&self) -> &'static str {
+        match self {
+            Self::Satisfied => "#d4edda",
+            Self::Mismatched => "#ffd8a8",
+            Self::Missing => "#f8d7da",
+            Self::Duplicated => "#fff3cd",
+            Self::Added => "#d4edda",
+            Self::Removed => "#f8d7da",
+            Self::Changed => "#cfe2ff",
+            Self::Unchanged => "#f1f3f5",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub label: String,
+    pub status: NodeStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    /// The declared range, or both ranges if it changed
+    pub label: String,
+    pub dev: bool,
+}
+
+/// A dependency graph flattened into nodes and edges for the diagram
+/// formats. Every package is a single node no matter how many packages
+/// depend on it.
+#[derive(Debug, Default)]
+pub struct Diagram {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub roots: Vec<usize>,
+    /// The number of nodes left out by `truncate`
+    pub omitted: usize,
+    ids: HashMap<String, usize>,
+}
+
+impl Diagram {
+    /// Every resolved package reachable from the graph's roots, with the
+    /// dependencies that are missing
+    pub fn from_graph(graph: &DependencyGraph) -> Self {
+        let mut diagram = Self::default();
+        let mut unsatisfied = HashSet::new();
+
+        for package in graph.packages() {
+            let key = PackageKey::from(package.as_ref());
+            let from = diagram.installed_node(package);
+            if graph.depth(&key) == Some(0) && !diagram.roots.contains(&from) {
+                diagram.roots.push(from);
+            }
+
+            let dependencies = sorted_values(&package.dependencies)
+                .into_iter()
+                .map(|d| (d, false))
+                .chain(
+                    sorted_values(&package.dev_dependencies)
+                        .into_iter()
+                        .map(|d| (d, true)),
+                );
+            for (dependency, dev) in dependencies {
+                let to = match &dependency.package {
+                    PackageEntry::Resolved(key) => match graph.get(key) {
+                        Some(package) => diagram.installed_node(package),
+                        None => diagram.package_node(key),
+                    },
+                    PackageEntry::Missing => diagram.node(
+                        format!("missing {}", dependency.name),
+                        format!("{} [MISSING]", dependency.name),
+                        NodeStatus::Missing,
+                    ),
+                    PackageEntry::Truncated => continue,
+                };
+                if dependency.package.satisfies(&dependency.version_req) == Some(false) {
+                    unsatisfied.insert(to);
+                }
+                diagram.edge(from, to, dependency.version_req.to_string(), dev);
+            }
+        }
+
+        // A folder reached through different resolvers is still one copy
+        let mut copies = HashMap::new();
+        for package in graph.packages() {
+            copies
+                .entry(package.name.as_str())
+                .or_insert_with(HashSet::new)
+                .insert(&package.data.install_path);
+        }
+        for package in graph.packages() {
+            let id = diagram.ids[&installed_id(package)];
+            if unsatisfied.contains(&id) {
+                diagram.nodes[id].status = NodeStatus::Mismatched;
+            } else if copies[package.name.as_str()].len() > 1 {
+                diagram.nodes[id].status = NodeStatus::Duplicated;
+            }
+        }
+
+        let mut unique = HashSet::new();
+        diagram
+            .edges
+            .retain(|e| unique.insert((e.from, e.to, e.label.clone(), e.dev)));
+
+        diagram
+    }
+
+    /// The changed part of each diff: the packages that changed or were added
+    /// or removed, and the unchanged packages on the paths to them. The diff
+    /// filter decides which changes are kept.
+    pub fn from_diffs(differs: &[Rc<Differ>]) -> Self {
+        let mut diagram = Self::default();
+        let mut kept = HashSet::new();
+
+        for differ in differs {
+            let filter = &differ.filter;
+            let root_key = differ.root_key();
+            let root = diagram.changed_node(&root_key);
+            diagram.roots.push(root);
+            kept.insert(root);
+
+            let mut queue = VecDeque::from([(root_key.clone(), 0)]);
+            let mut seen = HashSet::from([root_key]);
+            while let Some((key, depth)) = queue.pop_front() {
+                let Some(package) = differ.get_package(&key) else {
+                    continue;
+                };
+                if filter.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                    continue;
+                }
+                let from = diagram.ids[&changed_id(&key)];
+
+                let mut dependencies = Vec::new();
+                if filter.shows_dependencies(depth, false) {
+                    dependencies.extend(
+                        sorted_values(&package.dependencies)
+                            .into_iter()
+                            .map(|d| (d, false)),
+                    );
+                }
+                if filter.shows_dependencies(depth, true) {
+                    dependencies.extend(
+                        sorted_values(&package.dev_dependencies)
+                            .into_iter()
+                            .map(|d| (d, true)),
+                    );
+                }
+                for (dependency, dev) in dependencies {
+                    if filter.excludes(&dependency) {
+                        continue;
+                    }
+                    let included = filter.includes(&dependency);
+
+                    let (to, matched, label) = match &dependency.package {
+                        DiffedPackageAndVersionReq::Changed {
+                            package,
+                            version_req_left,
+                            version_req_right,
+                        } => {
+                            let label = pair_label(
+                                &version_req_left.to_string(),
+                                &version_req_right.to_string(),
+                            );
+                            match package {
+                                ChangedPackageEntry::Resolved(child) => {
+                                    let renamed = child.left.name != child.right.name
+                                        || dependency.renamed_from.is_some();
+                                    let changed = child.left.version != child.right.version;
+                                    // Identical subtrees aren't diffed any further
+                                    if !renamed && !changed && differ.get_package(child).is_none() {
+                                        continue;
+                                    }
+                                    let to = diagram.changed_node(child);
+                                    if seen.insert(child.clone()) {
+                                        queue.push_back((child.clone(), depth + 1));
+                                    }
+                                    let matched = included
+                                        && ((renamed && filter.only.is_empty())
+                                            || (changed && filter.matches_bump(child.bump())));
+                                    (to, matched, label)
+                                }
+                                ChangedPackageEntry::MismatchedResolution { left, right } => {
+                                    let to = diagram.node(
+                                        format!("mismatch {} {}", from, dependency.name),
+                                        format!(
+                                            "{} {}",
+                                            dependency.name,
+                                            pair_label(&entry_version(left), &entry_version(right))
+                                        ),
+                                        NodeStatus::Changed,
+                                    );
+                                    (to, included && filter.matches(package.kind()), label)
+                                }
+                                ChangedPackageEntry::Missing | ChangedPackageEntry::Truncated => {
+                                    continue;
+                                }
+                            }
+                        }
+                        DiffedPackageAndVersionReq::Added {
+                            package,
+                            version_req,
+                        } => (
+                            diagram.entry_node(&dependency.name, package, NodeStatus::Added),
+                            included && filter.only.is_empty(),
+                            version_req.to_string(),
+                        ),
+                        DiffedPackageAndVersionReq::Removed {
+                            package,
+                            version_req,
+                        } => (
+                            diagram.entry_node(&dependency.name, package, NodeStatus::Removed),
+                            included && filter.only.is_empty(),
+                            version_req.to_string(),
+                        ),
+                    };
+
+                    if matched {
+                        kept.insert(to);
+                    }
+                    diagram.edge(from, to, label, dev);
+                }
+            }
+        }
+
+        let mut unique = HashSet::new();
+        diagram
+            .edges
+            .retain(|e| unique.insert((e.from, e.to, e.label.clone())));
+        diagram.retain_reaching(&kept);
+        diagram
+    }
+
+    /// Adds a workspace member that only exists on one side of a diff
+    pub fn add_package(&mut self, package: &Package, status: NodeStatus) {
+        let key = PackageKey::from(package);
+        let id = self.node(package_id(&key), key.to_string(), status);
+        self.roots.push(id);
+    }
+
+    /// Removes the unchanged packages below the direct dependencies, linking
+    /// their dependents straight to their dependencies. The edge labels of
+    /// such shortcuts start with `...`.
+    pub fn collapse_unchanged(&mut self) {
+        let direct = self
+            .edges
+            .iter()
+            .filter(|edge| self.roots.contains(&edge.from))
+            .map(|edge| edge.to)
+            .collect::<HashSet<_>>();
+        let (collapsed, kept): (Vec<usize>, Vec<usize>) = (0..self.nodes.len()).partition(|id| {
+            self.nodes[*id].status == NodeStatus::Unchanged
+                && !self.roots.contains(id)
+                && !direct.contains(id)
+        });
+
+        for id in collapsed {
+            let (incoming, edges): (Vec<Edge>, Vec<Edge>) = std::mem::take(&mut self.edges)
+                .into_iter()
+                .partition(|edge| edge.to == id);
+            let (outgoing, edges): (Vec<Edge>, Vec<Edge>) =
+                edges.into_iter().partition(|edge| edge.from == id);
+            self.edges = edges;
+
+            for incoming in incoming.iter().filter(|edge| edge.from != id) {
+                for outgoing in outgoing.iter().filter(|edge| edge.to != id) {
+                    if incoming.from == outgoing.to {
+                        continue;
+                    }
+                    let label = if outgoing.label.starts_with("...") {
+                        outgoing.label.clone()
+                    } else {
+                        format!("... {}", outgoing.label)
+                    };
+                    self.edges.push(Edge {
+                        from: incoming.from,
+                        to: outgoing.to,
+                        label,
+                        dev: incoming.dev,
+                    });
+                }
+            }
+        }
+
+        let mut unique = HashSet::new();
+        self.edges
+            .retain(|e| unique.insert((e.from, e.to, e.label.clone())));
+        self.retain_nodes(&kept.into_iter().collect());
+    }
+
+    /// Keeps the first `max_nodes` nodes in breadth-first order from the roots
+    pub fn truncate(&mut self, max_nodes: usize) {
+        if self.nodes.len() <= max_nodes {
+            return;
+        }
+
+        let mut order = self.roots.clone();
+        let mut seen = order.iter().copied().collect::<HashSet<_>>();
+        let mut i = 0;
+        while i < order.len() && order.len() < max_nodes {
+            let from = order[i];
+            for edge in self.edges.iter().filter(|edge| edge.from == from) {
+                if seen.insert(edge.to) {
+                    order.push(edge.to);
+                }
+            }
+            i += 1;
+        }
+        order.truncate(max_nodes);
+
+        self.omitted += self.nodes.len() - order.len();
+        self.retain_nodes(&order.into_iter().collect());
+    }
+
+    /// Returns the node with the given id, creating it if needed
+    fn node(&mut self, id: String, label: String, status: NodeStatus) -> usize {
+        *self.ids.entry(id).or_insert_with(|| {
+            self.nodes.push(Node { label, status });
+            self.nodes.len() - 1
+        })
+    }
+
+    fn package_node(&mut self, key: &PackageKey) -> usize {
+        self.node(package_id(key), key.to_string(), NodeStatus::Satisfied)
+    }
+
+    /// A node for an installed package, shared by every key of its folder
+    fn installed_node(&mut self, package: &Package) -> usize {
+        let label = PackageKey::from(package).to_string();
+        self.node(installed_id(package), label, NodeStatus::Satisfied)
+    }
+
+    fn changed_node(&mut self, key: &ChangedPackageKey) -> usize {
+        let label = changed_label(key);
+        let status = if key.left.name == key.right.name && key.left.version == key.right.version {
+            NodeStatus::Unchanged
+        } else {
+            NodeStatus::Changed
+        };
+        self.node(changed_id(key), label, status)
+    }
+
+    /// A node for an added or removed dependency
+    fn entry_node(&mut self, name: &str, entry: &PackageEntry, status: NodeStatus) -> usize {
+        let (id, label) = match entry {
+            PackageEntry::Resolved(key) => (package_id(key), key.to_string()),
+            _ => (
+                name.to_string(),
+                format!("{} {}", name, entry_version(entry)),
+            ),
+        };
+        self.node(format!("{:?} {}", status, id), label, status)
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: String, dev: bool) {
+        self.edges.push(Edge {
+            from,
+            to,
+            label,
+            dev,
+        });
+    }
+
+    /// Drops the nodes that neither are in `kept` nor lead to one
+    fn retain_reaching(&mut self, kept: &HashSet<usize>) {
+        let mut reaching = kept.clone();
+        loop {
+            let before = reaching.len();
+            for edge in &self.edges {
+                if reaching.contains(&edge.to) {
+                    reaching.insert(edge.from);
+                }
+            }
+            if reaching.len() == before {
+                break;
+            }
+        }
+
+        self.retain_nodes(&reaching);
+    }
+
+    /// Drops the nodes that aren't in `kept`, along with their edges
+    fn retain_nodes(&mut self, kept: &HashSet<usize>) {
+        let mut new_ids = HashMap::new();
+        let nodes = std::mem::take(&mut self.nodes);
+        for (id, node) in nodes.into_iter().enumerate() {
+            if kept.contains(&id) {
+                new_ids.insert(id, self.nodes.len());
+                self.nodes.push(node);
+            }
+        }
+        self.edges.retain_mut(
+            |edge| match (new_ids.get(&edge.from), new_ids.get(&edge.to)) {
+                (Some(from), Some(to)) => {
+                    edge.from = *from;
+                    edge.to = *to;
+                    true
+                }
+                _ => false,
+            },
+        );
+        self.roots = self
+            .roots
+            .iter()
+            .filter_map(|id| new_ids.get(id).copied())
+            .collect();
+        self.ids.retain(|_, id| match new_ids.get(id) {
+            Some(new_id) => {
+                *id = *new_id;
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+fn package_id(key: &PackageKey) -> String {
+    format!("{} {}", key, key.node_modules_id)
+}
+
+fn installed_id(package: &Package) -> String {
+    package.data.install_path.display().to_string()
+}
+
+fn changed_id(key: &ChangedPackageKey) -> String {
+    format!("{} -> {}", package_id(&key.left), package_id(&key.right))
+}
+
+/// `name@left -> right`, with both names if the package was renamed
+fn changed_label(key: &ChangedPackageKey) -> String {
+    let name = pair_label(&key.left.name, &key.right.name);
+    let version = pair_label(&key.left.version_str(), &key.right.version_str());
+    if version.is_empty() {
+        name
+    } else {
+        format!("{}@{}", name, version)
+    }
+}
+
+/// `left -> right`, or just one of them if they are equal
+fn pair_label(left: &str, right: &str) -> String {
+    if left == right {
+        left.to_string()
+    } else {
+        format!("{} -> {}", left, right)
+    }
+}
+
+fn entry_version(entry: &PackageEntry) -> String {
+    match entry {
+        PackageEntry::Resolved(key) => key.version_str(),
+        PackageEntry::Missing => "[MISSING]".to_string(),
+        PackageEntry::Truncated => "[TRUNCATED]".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_from_graph_dedupes_installed_folders() {
+        // The member @workspace/foo is also my-app's dependency through a symlink
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/workspace")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let diagram = Diagram::from_graph(&graph);
+
+        let mut nodes = diagram
+            .nodes
+            .iter()
+            .map(|n| (n.label.as_str(), n.status))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|(label, _)| *label);
+        assert_eq!(
+            nodes,
+            [
+                ("@workspace/foo@0.0.1", NodeStatus::Satisfied),
+                ("my-app", NodeStatus::Satisfied),
+                ("test-workspace-root", NodeStatus::Satisfied),
+            ]
+        );
+        assert_eq!(diagram.roots.len(), 3);
+        assert_eq!(diagram.edges.len(), 1);
+
+        let dot = dot::render(&diagram);
+        assert_eq!(dot.matches("label=\"@workspace/foo@0.0.1\"").count(), 1);
+    }
+
+    #[test]
+    fn test_collapse_and_truncate() {
+        let node = |label: &str, status| Node {
+            label: label.to_string(),
+            status,
+        };
+        let edge = |from, to, label: &str| Edge {
+            from,
+            to,
+            label: label.to_string(),
+            dev: false,
+        };
+        let mut diagram = Diagram {
+            nodes: vec![
+                node("app", NodeStatus::Unchanged),
+                node("direct@1.0.0", NodeStatus::Unchanged),
+                node("transitive@1.0.0", NodeStatus::Unchanged),
+                node("changed@1.0.0 -> 2.0.0", NodeStatus::Changed),
+            ],
+            edges: vec![edge(0, 1, "^1"), edge(1, 2, "^1"), edge(2, 3, "^2")],
+            roots: vec![0],
+            ..Default::default()
+        };
+
+        diagram.collapse_unchanged();
+        let labels = diagram
+            .nodes
+            .iter()
+            .map(|n| n.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["app", "direct@1.0.0", "changed@1.0.0 -> 2.0.0"]);
+        assert_eq!(diagram.edges[1].from, 1);
+        assert_eq!(diagram.edges[1].to, 2);
+        assert_eq!(diagram.edges[1].label, "... ^2");
+
+        diagram.truncate(2);
+        assert_eq!(diagram.nodes.len(), 2);
+        assert_eq!(diagram.edges.len(), 1);
+        assert_eq!(diagram.omitted, 1);
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    lockfile::{self, LockedPackage},
+    package::{Dependency, Package, PackageEntry, PackageKey},
+    ptree_impl::sorted_values,
+};
+
+struct Context<'a> {
+    root: &'a Package,
+    locked: HashMap<PathBuf, LockedPackage>,
+    /// Packages whose dependencies have already been listed
+    seen: HashSet<PackageKey>,
+    /// Every problem in the tree, for the root's `problems`
+    problems: Vec<String>,
+}
+
+/// Renders the tree the way `npm ls --all --json` does. Workspace members are
+/// listed among the root's dependencies, like npm lists them, and a package's
+/// dependencies are only listed the first time it is reached.
+pub fn render(root: &Rc<Package>, members: &[Rc<Package>]) -> Value {
+    let mut context = Context {
+        root,
+        locked: lockfile::read_locked_packages(&root.data.install_path),
+        seen: HashSet::from([PackageKey::from(root.as_ref())]),
+        problems: Vec::new(),
+    };
+
+    let mut dependencies = dependencies(&mut context, root);
+    for member in members {
+        if dependencies.contains_key(&member.name) {
+            continue;
+        }
+        let mut item = json!({
+            "resolved": link(member, root),
+            "overridden": false,
+        });
+        if let Some(version) = &member.version {
+            item["version"] = json!(version.to_string());
+        }
+        if context.seen.insert(PackageKey::from(member.as_ref())) {
+            let member_dependencies = self::dependencies(&mut context, member);
+            if !member_dependencies.is_empty() {
+                item["dependencies"] = Value::Object(member_dependencies);
+            }
+        }
+        dependencies.insert(member.name.clone(), item);
+    }
+
+    let mut value = json!({ "name": root.name });
+    if let Some(version) = &root.version {
+        value["version"] = json!(version.to_string());
+    }
+    if !context.problems.is_empty() {
+        value["problems"] = json!(context.problems);
+        // npm ls exits with this error when there are problems
+        value["error"] = json!({
+            "code": "ELSPROBLEMS",
+            "summary": context.problems.join("\n"),
+            "detail": "",
+        });
+    }
+    if !dependencies.is_empty() {
+        value["dependencies"] = Value::Object(dependencies);
+    }
+    value
+}
+
+/// The package's dependencies, and its dev dependencies if it is first-party
+fn dependencies(context: &mut Context, package: &Package) -> Map<String, Value> {
+    let mut items = Map::new();
+    let dependencies = sorted_values(&package.dependencies)
+        .into_iter()
+        .chain(sorted_values(&package.dev_dependencies));
+    for dependency in dependencies {
+        if let Some(item) = dependency_item(context, package, &dependency) {
+            items.insert(dependency.name.clone(), item);
+        }
+    }
+    items
+}
+
+fn dependency_item(
+    context: &mut Context,
+    parent: &Package,
+    dependency: &Dependency,
+) -> Option<Value> {
+    let key = match &dependency.package {
+        PackageEntry::Resolved(key) => key,
+        PackageEntry::Missing => {
+            let problem = format!(
+                "missing: {}@{}, required by {}",
+                dependency.name,
+                dependency.version_req,
+                PackageKey::from(parent)
+            );
+            context.problems.push(problem.clone());
+            return Some(json!({
+                "required": dependency.version_req.to_string(),
+                "missing": true,
+                "problems": [problem],
+            }));
+        }
+        // npm has no equivalent of stopping at a depth while still listing the package
+        PackageEntry::Truncated => return None,
+    };
+    let package = parent.resolver()?.get_package(key)?;
+
+    let overridden = context.root.data.overrides.contains(&dependency.name);
+    let resolved = package.data.resolved.clone().or_else(|| {
+        context
+            .locked
+            .get(&package.data.install_path)
+            .and_then(|locked| locked.resolved.clone())
+    });
+    // Symlinked packages, such as workspace members, live outside node_modules
+    let resolved = resolved.or_else(|| {
+        package
+            .data
+            .install_path
+            .components()
+            .all(|component| component.as_os_str() != "node_modules")
+            .then(|| link(&package, context.root))
+    });
+
+    let mut item = json!({ "overridden": overridden });
+    if let Some(version) = &package.version {
+        item["version"] = json!(version.to_string());
+    }
+    if let Some(resolved) = resolved {
+        item["resolved"] = json!(resolved);
+    }
+
+    // An override replaces the declared range, so npm doesn't hold the package to it
+    if dependency.package.satisfies(&dependency.version_req) == Some(false) && !overridden {
+        let problem = format!("invalid: {} {}", key, package.data.install_path.display());
+        item["invalid"] = json!(format!(
+            "\"{}\" from {}",
+            dependency.version_req,
+            location(context.root, parent)
+        ));
+        item["problems"] = json!([problem]);
+        context.problems.push(problem);
+    }
+
+    if context.seen.insert(key.clone()) {
+        let children = dependencies(context, &package);
+        if !children.is_empty() {
+            item["dependencies"] = Value::Object(children);
+        }
+    }
+    Some(item)
+}
+
+/// npm resolves links, such as workspace members, relative to node_modules
+fn link(package: &Package, root: &Package) -> String {
+    format!(
+        "file:../{}",
+        relative(&package.data.install_path, &root.data.install_path)
+    )
+}
+
+/// How npm names the package that declares a dependency
+fn location(root: &Package, package: &Package) -> String {
+    if package.data.install_path == root.data.install_path {
+        "the root project".to_string()
+    } else {
+        relative(&package.data.install_path, &root.data.install_path)
+    }
+}
+
+fn relative(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_render_matches_npm() {
+        // What `npm ls --all --json` prints in tests/diff/left. The fixture's
+        // node_modules are written by hand rather than installed by npm, so
+        // nothing has a registry `resolved` URL; only links do.
+        let expected: Value =
+            serde_json::from_str(include_str!("../../tests/diff/npm-ls-left.json")).unwrap();
+
+        let mut resolver = Resolver::new(usize::MAX);
+        let packages =
+            crate::resolve_with_members(&mut resolver, Path::new("tests/diff/left")).unwrap();
+        assert_eq!(render(&packages[0], &packages[1..]), expected);
+    }
+}
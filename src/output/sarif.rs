@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use serde_json::{Value, json};
+
+use crate::check::{Finding, Problem};
+
+/// `(id, description)` of each kind of problem, in the order of `rule_index`
+const RULES: [(&str, &str); 5] = [
+    (
+        "mismatched-range",
+        "The installed version doesn't satisfy the declared range",
+    ),
+    (
+        "missing-dependency",
+        "No version of the dependency is installed",
+    ),
+    (
+        "peer-dependency",
+        "A peer dependency is missing or doesn't satisfy the declared range",
+    ),
+    (
+        "engine-violation",
+        "The package's engines.node excludes the target Node version",
+    ),
+    (
+        "license-violation",
+        "The package's license violates the license policy",
+    ),
+];
+
+/// Renders the findings as a SARIF 2.1.0 log for code scanning tools. Each
+/// result points at the offending entry of the package's package.json, with
+/// paths relative to the current directory when they are inside it.
+pub fn render(findings: &[Finding]) -> Result<String> {
+    let current_dir = std::env::current_dir()?.canonicalize()?;
+
+    let rules = RULES
+        .iter()
+        .map(|(id, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": "error" },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = findings
+        .iter()
+        .map(|finding| {
+            let index = rule_index(&finding.problem);
+            let manifest = finding.install_path.join("package.json");
+            let mut location = json!({
+                "artifactLocation": { "uri": uri(&manifest, &current_dir) },
+            });
+            if let Some(position) = finding.position {
+                location["region"] = json!({
+                    "startLine": position.line,
+                    "startColumn": position.column,
+                });
+            }
+            json!({
+                "ruleId": RULES[index].0,
+                "ruleIndex": index,
+                "level": "error",
+                "message": { "text": finding.message() },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::to_string_pretty(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "nmvc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "utf16CodeUnits",
+            "results": results,
+        }],
+    }))?)
+}
+
+fn rule_index(problem: &Problem) -> usize {
+    match problem {
+        Problem::Mismatch { .. } => 0,
+        Problem::Missing { .. } => 1,
+        Problem::Peer { .. } => 2,
+        Problem::Engine { .. } => 3,
+        Problem::License { .. } => 4,
+    }
+}
+
+fn uri(path: &Path, current_dir: &Path) -> Value {
+    match path.strip_prefix(current_dir) {
+        Ok(relative) => json!(relative.to_string_lossy().replace('\\', "/")),
+        Err(_) => json!(format!(
+            "file://{}",
+            path.to_string_lossy().replace('\\', "/")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check, graph::DependencyGraph, resolver::Resolver};
+
+    #[test]
+    fn test_render() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/check")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let mut findings = check::check_dependencies(&graph);
+        findings.extend(check::check_peers(&graph));
+        check::locate(&mut findings, &graph);
+
+        let log: Value = serde_json::from_str(&render(&findings).unwrap()).unwrap();
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let results = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| {
+                let index = result["ruleIndex"].as_u64().unwrap() as usize;
+                assert_eq!(rules[index]["id"], result["ruleId"]);
+                let location = &result["locations"][0]["physicalLocation"];
+                format!(
+                    "{} {}:{}:{}",
+                    result["ruleId"].as_str().unwrap(),
+                    location["artifactLocation"]["uri"].as_str().unwrap(),
+                    location["region"]["startLine"],
+                    location["region"]["startColumn"]
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Tests run from the crate root, so the paths are relative to it
+        assert_eq!(
+            results,
+            [
+                "missing-dependency tests/check/package.json:6:3",
+                "mismatched-range tests/check/package.json:8:3",
+                "missing-dependency tests/check/package.json:9:3",
+                "peer-dependency tests/check/node_modules/plugin/package.json:5:3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uri() {
+        let current_dir = Path::new("/work/project");
+        assert_eq!(
+            uri(Path::new("/work/project/app/package.json"), current_dir),
+            "app/package.json"
+        );
+        assert_eq!(
+            uri(Path::new("/work/other/package.json"), current_dir),
+            "file:///work/other/package.json"
+        );
+    }
+}
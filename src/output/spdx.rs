@@ -0,0 +1,531 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use serde_json::json;
+
+use crate::{package::PackageKey, sbom::Component};
+
+const NOASSERTION: &str = "NOASSERTION";
+
+/// The parts of an SPDX 2.3 document shared by the JSON and tag-value formats
+struct Document<'a> {
+    name: String,
+    namespace: String,
+    created: String,
+    packages: Vec<SpdxPackage<'a>>,
+    /// `(element, relationship, related element)`
+    relationships: Vec<(String, &'static str, String)>,
+    /// The `LicenseRef-` ids used by the packages, with the ids as declared
+    license_refs: BTreeMap<String, String>,
+}
+
+struct SpdxPackage<'a> {
+    id: String,
+    component: &'a Component,
+}
+
+impl<'a> Document<'a> {
+    fn new(components: &'a [Component]) -> Result<Self> {
+        let root = components
+            .first()
+            .ok_or(eyre!("There are no packages to describe"))?;
+
+        let mut used = HashSet::new();
+        let packages = components
+            .iter()
+            .map(|component| SpdxPackage {
+                id: unique_id(component, &mut used),
+                component,
+            })
+            .collect::<Vec<_>>();
+        let ids = packages
+            .iter()
+            .map(|package| (package.component.purl.as_str(), package.id.clone()))
+            .collect::<HashMap<_, _>>();
+
+        // Workspace members are described by the document alongside the root,
+        // everything else is only reachable through relationships
+        let mut relationships = packages
+            .iter()
+            .filter(|package| package.component.first_party)
+            .map(|package| {
+                (
+                    "SPDXRef-DOCUMENT".to_string(),
+                    "DESCRIBES",
+                    package.id.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for package in &packages {
+            for (purl, dev) in &package.component.dependencies {
+                let Some(dependency) = ids.get(purl.as_str()) else {
+                    continue;
+                };
+                relationships.push(if *dev {
+                    (dependency.clone(), "DEV_DEPENDENCY_OF", package.id.clone())
+                } else {
+                    (package.id.clone(), "DEPENDS_ON", dependency.clone())
+                });
+            }
+        }
+
+        let license_refs = components
+            .iter()
+            .filter_map(|component| component.spdx_license())
+            .flat_map(|license| license.references)
+            .collect();
+
+        let created = timestamp(created_seconds());
+        let mut hasher = DefaultHasher::new();
+        created.hash(&mut hasher);
+        for (element, relationship, related) in &relationships {
+            (element, relationship, related).hash(&mut hasher);
+        }
+        let name = PackageKey::from(root.package.as_ref()).to_string();
+        let namespace = format!(
+            "https://spdx.org/spdxdocs/{}-{:016x}",
+            sanitize(&name),
+            hasher.finish()
+        );
+
+        Ok(Self {
+            name,
+            namespace,
+            created,
+            packages,
+            relationships,
+            license_refs,
+        })
+    }
+}
+
+/// Renders an SPDX 2.3 JSON document
+pub fn render_json(components: &[Component]) -> Result<String> {
+    let document = Document::new(components)?;
+
+    let packages = document
+        .packages
+        .iter()
+        .map(|package| {
+            let component = package.component;
+            let mut value = json!({
+                "SPDXID": package.id,
+                "name": component.package.name,
+                "downloadLocation": NOASSERTION,
+                "filesAnalyzed": false,
+                "licenseConcluded": NOASSERTION,
+                "licenseDeclared": declared_license(component),
+                "primaryPackagePurpose": purpose(component),
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": component.purl,
+                }],
+            });
+            if let Some(version) = &component.package.version {
+                value["versionInfo"] = json!(version.to_string());
+            }
+            if let Some(comment) = license_comment(component) {
+                value["licenseComments"] = json!(comment);
+            }
+            let checksums = checksums(component)
+                .map(|(algorithm, hex)| json!({ "algorithm": algorithm, "checksumValue": hex }))
+                .collect::<Vec<_>>();
+            if !checksums.is_empty() {
+                value["checksums"] = json!(checksums);
+            }
+            value
+        })
+        .collect::<Vec<_>>();
+
+    let relationships = document
+        .relationships
+        .iter()
+        .map(|(element, relationship, related)| {
+            json!({
+                "spdxElementId": element,
+                "relationshipType": relationship,
+                "relatedSpdxElement": related,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut value = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": document.name,
+        "documentNamespace": document.namespace,
+        "creationInfo": {
+            "created": document.created,
+            "creators": [tool()],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    });
+    if !document.license_refs.is_empty() {
+        value["hasExtractedLicensingInfos"] = document
+            .license_refs
+            .iter()
+            .map(|(id, declared)| {
+                json!({
+                    "licenseId": id,
+                    "name": declared,
+                    "extractedText": extracted_text(declared),
+                })
+            })
+            .collect();
+    }
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+/// Renders an SPDX 2.3 tag-value document
+pub fn render_tag_value(components: &[Component]) -> Result<String> {
+    let document = Document::new(components)?;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "SPDXVersion: SPDX-2.3");
+    let _ = writeln!(out, "DataLicense: CC0-1.0");
+    let _ = writeln!(out, "SPDXID: SPDXRef-DOCUMENT");
+    let _ = writeln!(out, "DocumentName: {}", document.name);
+    let _ = writeln!(out, "DocumentNamespace: {}", document.namespace);
+    let _ = writeln!(out, "Creator: {}", tool());
+    let _ = writeln!(out, "Created: {}", document.created);
+
+    for package in &document.packages {
+        let component = package.component;
+        out.push('\n');
+        let _ = writeln!(out, "PackageName: {}", component.package.name);
+        let _ = writeln!(out, "SPDXID: {}", package.id);
+        if let Some(version) = &component.package.version {
+            let _ = writeln!(out, "PackageVersion: {}", version);
+        }
+        let _ = writeln!(out, "PackageDownloadLocation: {}", NOASSERTION);
+        let _ = writeln!(out, "FilesAnalyzed: false");
+        for (algorithm, hex) in checksums(component) {
+            let _ = writeln!(out, "PackageChecksum: {}: {}", algorithm, hex);
+        }
+        let _ = writeln!(out, "PackageLicenseConcluded: {}", NOASSERTION);
+        let _ = writeln!(
+            out,
+            "PackageLicenseDeclared: {}",
+            declared_license(component)
+        );
+        if let Some(comment) = license_comment(component) {
+            let _ = writeln!(out, "PackageLicenseComments: <text>{}</text>", comment);
+        }
+        let _ = writeln!(out, "PrimaryPackagePurpose: {}", purpose(component));
+        let _ = writeln!(out, "ExternalRef: PACKAGE-MANAGER purl {}", component.purl);
+    }
+
+    for (id, declared) in &document.license_refs {
+        out.push('\n');
+        let _ = writeln!(out, "LicenseID: {}", id);
+        let _ = writeln!(
+            out,
+            "ExtractedText: <text>{}</text>",
+            extracted_text(declared)
+        );
+        let _ = writeln!(out, "LicenseName: {}", declared);
+    }
+
+    out.push('\n');
+    for (element, relationship, related) in &document.relationships {
+        let _ = writeln!(
+            out,
+            "Relationship: {} {} {}",
+            element, relationship, related
+        );
+    }
+    Ok(out)
+}
+
+fn tool() -> String {
+    format!("Tool: nmvc-{}", env!("CARGO_PKG_VERSION"))
+}
+
+fn purpose(component: &Component) -> &'static str {
+    if component.first_party {
+        "APPLICATION"
+    } else {
+        "LIBRARY"
+    }
+}
+
+fn declared_license(component: &Component) -> String {
+    component
+        .spdx_license()
+        .map_or(NOASSERTION.to_string(), |license| license.expression)
+}
+
+/// Only the license id is known, not the license text
+fn extracted_text(declared: &str) -> String {
+    format!(
+        "The license declared as \"{}\", which is not on the SPDX License List",
+        declared
+    )
+}
+
+/// Keeps declared licenses that aren't SPDX expressions, such as `UNLICENSED`
+fn license_comment(component: &Component) -> Option<String> {
+    if component.spdx_license().is_some() {
+        return None;
+    }
+    let declared = component.license.expression.as_ref()?;
+    Some(format!("Declared as \"{}\"", declared))
+}
+
+fn checksums(component: &Component) -> impl Iterator<Item = (&'static str, &str)> {
+    component.hashes.iter().filter_map(|checksum| {
+        let algorithm = match checksum.algorithm.as_str() {
+            "sha1" => "SHA1",
+            "sha256" => "SHA256",
+            "sha384" => "SHA384",
+            "sha512" => "SHA512",
+            _ => return None,
+        };
+        Some((algorithm, checksum.hex.as_str()))
+    })
+}
+
+/// An SPDX identifier such as `SPDXRef-Package-npm-scope-name-1.0.0`, with a
+/// suffix when sanitizing makes two packages collide
+fn unique_id(component: &Component, used: &mut HashSet<String>) -> String {
+    let mut id = format!(
+        "SPDXRef-Package-npm-{}",
+        sanitize(&component.package.name.replace('@', ""))
+    );
+    if let Some(version) = &component.package.version {
+        id = format!("{}-{}", id, sanitize(&version.to_string()));
+    }
+    let mut unique = id.clone();
+    let mut suffix = 1;
+    while !used.insert(unique.clone()) {
+        suffix += 1;
+        unique = format!("{}-{}", id, suffix);
+    }
+    unique
+}
+
+/// SPDX identifiers may only contain letters, numbers, `.` and `-`
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Honors SOURCE_DATE_EPOCH so that builds can produce identical documents
+fn created_seconds() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default()
+        })
+}
+
+/// Formats seconds since the epoch as e.g. `2024-01-31T12:00:00Z`
+fn timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Converts days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{graph::DependencyGraph, resolver::Resolver, sbom};
+
+    /// The components of tests/sbom, whose dependencies declare licenses
+    /// that are misspelled, not on the SPDX license list or not expressions
+    fn components() -> Vec<Component> {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new("tests/sbom")).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, false);
+        sbom::collect_components(&graph, &graph)
+    }
+
+    #[test]
+    fn test_render_json_licenses() {
+        let components = components();
+        let document: serde_json::Value =
+            serde_json::from_str(&render_json(&components).unwrap()).unwrap();
+
+        let mut declared = document["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| {
+                (
+                    package["name"].as_str().unwrap(),
+                    package["licenseDeclared"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        declared.sort();
+        assert_eq!(
+            declared,
+            [
+                ("custom", "LicenseRef-Custom-License"),
+                ("dual", "(MIT OR LicenseRef-Acme-1.0)"),
+                ("exception", "GPL-2.0-or-later WITH Classpath-exception-2.0"),
+                ("lowercase", "Apache-2.0"),
+                ("private", NOASSERTION),
+                ("sbom-fixture", "MIT"),
+            ]
+        );
+        assert_eq!(
+            document["hasExtractedLicensingInfos"],
+            json!([
+                {
+                    "licenseId": "LicenseRef-Acme-1.0",
+                    "name": "Acme-1.0",
+                    "extractedText": "The license declared as \"Acme-1.0\", which is not on the SPDX License List",
+                },
+                {
+                    "licenseId": "LicenseRef-Custom-License",
+                    "name": "Custom_License",
+                    "extractedText": "The license declared as \"Custom_License\", which is not on the SPDX License List",
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_render_tag_value_licenses() {
+        let components = components();
+        let document = render_tag_value(&components).unwrap();
+
+        assert!(document.contains("PackageLicenseDeclared: LicenseRef-Custom-License\n"));
+        assert!(document.contains("PackageLicenseDeclared: (MIT OR LicenseRef-Acme-1.0)\n"));
+        assert!(document.contains("PackageLicenseDeclared: Apache-2.0\n"));
+        assert!(document.contains(
+            "PackageLicenseDeclared: NOASSERTION\nPackageLicenseComments: <text>Declared as \"UNLICENSED\"</text>\n"
+        ));
+        assert!(document.contains(
+            "\nLicenseID: LicenseRef-Acme-1.0\nExtractedText: <text>The license declared as \"Acme-1.0\", which is not on the SPDX License List</text>\nLicenseName: Acme-1.0\n"
+        ));
+        assert!(document.contains("\nLicenseID: LicenseRef-Custom-License\n"));
+    }
+
+    /// The relationships of a rendered document, with package names for ids
+    fn render_relationships(path: &str) -> Vec<(String, String, String)> {
+        let mut resolver = Resolver::new(usize::MAX);
+        let roots = crate::resolve_with_members(&mut resolver, Path::new(path)).unwrap();
+        let graph = DependencyGraph::from_roots(&roots, true);
+        let production = DependencyGraph::from_roots(&roots, false);
+        let components = sbom::collect_components(&graph, &production);
+        let document: serde_json::Value =
+            serde_json::from_str(&render_json(&components).unwrap()).unwrap();
+
+        let mut names = document["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| {
+                (
+                    package["SPDXID"].as_str().unwrap(),
+                    package["name"].as_str().unwrap(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        names.insert("SPDXRef-DOCUMENT", "DOCUMENT");
+        let mut relationships = document["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|relationship| {
+                let name = |field: &str| names[relationship[field].as_str().unwrap()].to_string();
+                (
+                    name("spdxElementId"),
+                    relationship["relationshipType"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                    name("relatedSpdxElement"),
+                )
+            })
+            .collect::<Vec<_>>();
+        relationships.sort();
+        relationships
+    }
+
+    #[test]
+    fn test_render_json_relationships() {
+        let relationship = |element: &str, kind: &str, related: &str| {
+            (element.to_string(), kind.to_string(), related.to_string())
+        };
+
+        // Dev dependencies point at their dependent, everything else away from it
+        let relationships = render_relationships("tests/sbom");
+        assert_eq!(
+            relationships,
+            [
+                relationship("@scope/tool", "DEPENDS_ON", "dual"),
+                relationship("@scope/tool", "DEV_DEPENDENCY_OF", "sbom-fixture"),
+                relationship("DOCUMENT", "DESCRIBES", "sbom-fixture"),
+                relationship("sbom-fixture", "DEPENDS_ON", "custom"),
+                relationship("sbom-fixture", "DEPENDS_ON", "dual"),
+                relationship("sbom-fixture", "DEPENDS_ON", "exception"),
+                relationship("sbom-fixture", "DEPENDS_ON", "lowercase"),
+                relationship("sbom-fixture", "DEPENDS_ON", "private"),
+            ]
+        );
+
+        // Every workspace member is described by the document
+        let relationships = render_relationships("tests/workspace");
+        assert_eq!(
+            relationships,
+            [
+                relationship("DOCUMENT", "DESCRIBES", "@workspace/foo"),
+                relationship("DOCUMENT", "DESCRIBES", "my-app"),
+                relationship("DOCUMENT", "DESCRIBES", "test-workspace-root"),
+                relationship("my-app", "DEPENDS_ON", "@workspace/foo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(timestamp(951_827_696), "2000-02-29T12:34:56Z");
+        assert_eq!(timestamp(1_767_225_599), "2025-12-31T23:59:59Z");
+    }
+}
@@ -1,6 +1,7 @@
 use colored::*;
 use ptree::PrintConfig;
 use semver::Version;
+use serde::Serialize;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
@@ -13,10 +14,11 @@ use crate::dependency_resolver::DependencyResolver;
 use crate::extended_version_req::ExtendedVersionReq;
 use crate::package_data::PackageJsonData;
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize)]
 pub struct PackageKey {
     pub name: String,
     pub version: Option<Version>, // Workspace packages may not have a version
+    #[serde(skip)]
     pub node_modules_id: u32,
 }
 
@@ -30,7 +32,7 @@ impl PackageKey {
         }
     }
 
-    fn version_str(&self) -> String {
+    pub(crate) fn version_str(&self) -> String {
         self.version
             .as_ref()
             .map(|v| v.to_string())
@@ -139,6 +141,8 @@ pub struct Package {
     pub dev_dependencies: HashMap<String, Dependency>,
     pub(crate) dep_resolver: Weak<DependencyResolver>,
     pub(crate) visited: RefCell<bool>,
+    // Deprecation message from registry metadata, if it has been looked up
+    pub(crate) deprecated: RefCell<Option<String>>,
     pub data: PackageJsonData,
 }
 
@@ -147,12 +151,24 @@ impl Package {
         self.dep_resolver.upgrade()
     }
 
+    pub(crate) fn deprecated_badge(&self) -> ColoredString {
+        if self.deprecated.borrow().is_some() {
+            " [DEPRECATED]".yellow()
+        } else {
+            "".into()
+        }
+    }
+
     pub fn print_tree(&self, config: &PrintConfig) -> io::Result<()> {
+        self.write_tree(io::stdout(), config)
+    }
+
+    pub fn write_tree<W: io::Write>(&self, f: W, config: &PrintConfig) -> io::Result<()> {
         debug!("Printing tree for {}", self);
         self.resolver()
             .expect("Dependency resolver is missing")
             .refresh_visited();
-        ptree::print_tree_with(self, config)?;
+        ptree::write_tree_with(self, f, config)?;
         Ok(())
     }
 }
@@ -164,17 +180,19 @@ impl fmt::Display for Package {
         } else {
             "".into()
         };
+        let deprecated_text = self.deprecated_badge();
         if let Some(version) = &self.version {
             write!(
                 f,
-                "{}{}{}{}",
+                "{}{}{}{}{}",
                 self.name,
                 "@".bright_black(),
                 version.to_string().blue(),
+                deprecated_text,
                 deduped_text
             )
         } else {
-            write!(f, "{}{}", self.name, deduped_text)
+            write!(f, "{}{}{}", self.name, deprecated_text, deduped_text)
         }
     }
 }
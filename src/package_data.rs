@@ -13,6 +13,7 @@ use serde_json::Value;
 
 use crate::{
     extended_version_req::ExtendedVersionReq,
+    json_positions::SourcePositions,
     node_modules::{NEXT_PARENT_ID, NodeModules},
     workspace_data::WorkspaceData,
 };
@@ -25,7 +26,30 @@ pub struct PackageJsonData {
     pub parent_id: u32,
     pub dependencies: HashMap<String, ExtendedVersionReq>,
     pub dev_dependencies: HashMap<String, ExtendedVersionReq>,
+    pub peer_dependencies: HashMap<String, ExtendedVersionReq>,
+    /// Peers marked optional in `peerDependenciesMeta`, which may be left uninstalled
+    pub optional_peers: Vec<String>,
     pub workspace_data: Option<WorkspaceData>,
+    /// Source globs from `nmvc.sources`, used when scanning for imports
+    pub source_globs: Option<Vec<String>>,
+    pub scripts: HashMap<String, String>,
+    /// Names of the executables the package installs through `bin`
+    pub bins: Vec<String>,
+    pub engines: HashMap<String, ExtendedVersionReq>,
+    /// The declared SPDX license expression
+    pub license: Option<String>,
+    /// The source repository, normalized to e.g. `github.com/user/repo`
+    pub repository: Option<String>,
+    /// The Subresource Integrity of the tarball, which older npm versions
+    /// record in installed package.json files as `_integrity`
+    pub integrity: Option<String>,
+    /// Where the tarball was fetched from, recorded by older npm versions as `_resolved`
+    pub resolved: Option<String>,
+    /// Names of the packages whose versions are forced through `overrides`,
+    /// `resolutions` or `pnpm.overrides`
+    pub overrides: Vec<String>,
+    /// Where the keys of the package.json are, to point problems at a line
+    pub positions: SourcePositions,
 }
 
 impl PackageJsonData {
@@ -34,19 +58,20 @@ impl PackageJsonData {
     /// Returns an Err if the package.json is invalid.
     pub fn new_root(folder: &Path) -> Result<Option<Self>> {
         let id = NEXT_PARENT_ID.fetch_add(1, Ordering::SeqCst);
-        return Self::from_folder_with_id(folder, id);
+        Self::from_folder_with_id(folder, id)
     }
 
     pub(crate) fn from_folder_with_id(folder: &Path, node_modules_id: u32) -> Result<Option<Self>> {
-        if let Some(value) = read_package_json(&folder.join("package.json"))? {
-            return Self::from_value(value, node_modules_id, folder);
+        if let Some((value, positions)) = read_package_json(&folder.join("package.json"))? {
+            Self::from_value(value, positions, node_modules_id, folder)
         } else {
-            return Ok(None);
+            Ok(None)
         }
     }
 
     pub(crate) fn from_value(
         dep_json: serde_json::Value,
+        positions: SourcePositions,
         node_modules_id: u32,
         install_path: &Path,
     ) -> Result<Option<Self>> {
@@ -58,7 +83,7 @@ impl PackageJsonData {
         let version = dep_json
             .get("version")
             .and_then(|v| v.as_str())
-            .map(|v| Version::parse(v))
+            .map(Version::parse)
             .transpose()?;
         let dependencies = dep_json
             .get("dependencies")
@@ -77,6 +102,24 @@ impl PackageJsonData {
                 .unwrap_or_default()
         };
 
+        let peer_dependencies = dep_json
+            .get("peerDependencies")
+            .map(deps_from_value)
+            .transpose()?
+            .unwrap_or_default();
+        let optional_peers = dep_json
+            .get("peerDependenciesMeta")
+            .and_then(|meta| meta.as_object())
+            .map(|meta| {
+                meta.iter()
+                    .filter(|(_, meta)| {
+                        meta.get("optional").and_then(|o| o.as_bool()) == Some(true)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let install_path = install_path.canonicalize()?;
 
         let globs = get_workspace_globs(&dep_json, &install_path)?;
@@ -84,6 +127,59 @@ impl PackageJsonData {
             .map(|globs| WorkspaceData::from_globs(&globs))
             .transpose()?;
 
+        let source_globs = dep_json
+            .get("nmvc")
+            .and_then(|config| config.get("sources"))
+            .map(|sources| string_array_from_value(sources, "nmvc.sources", &install_path))
+            .transpose()?;
+
+        let scripts = dep_json
+            .get("scripts")
+            .and_then(|scripts| scripts.as_object())
+            .map(|scripts| {
+                scripts
+                    .iter()
+                    .filter_map(|(name, script)| Some((name.clone(), script.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let bins = match dep_json.get("bin") {
+            // A single executable is named after the package (without its scope)
+            Some(Value::String(_)) => vec![name.rsplit('/').next().unwrap_or(&name).to_string()],
+            Some(Value::Object(bins)) => bins.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+
+        // Installed packages sometimes have malformed engines, which npm ignores as well
+        let engines = dep_json
+            .get("engines")
+            .and_then(|engines| engines.as_object())
+            .map(|engines| {
+                engines
+                    .iter()
+                    .filter_map(|(name, req)| {
+                        Some((
+                            name.clone(),
+                            ExtendedVersionReq::parse_engine(req.as_str()?),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let license = license_from_value(&dep_json);
+        let repository = repository_from_value(&dep_json);
+        let integrity = dep_json
+            .get("_integrity")
+            .and_then(|integrity| integrity.as_str())
+            .map(|integrity| integrity.to_string());
+        let resolved = dep_json
+            .get("_resolved")
+            .and_then(|resolved| resolved.as_str())
+            .map(|resolved| resolved.to_string());
+        let overrides = overrides_from_value(&dep_json);
+
         Ok(Some(Self {
             name,
             version,
@@ -91,7 +187,19 @@ impl PackageJsonData {
             parent_id: node_modules_id,
             dependencies,
             dev_dependencies,
+            peer_dependencies,
+            optional_peers,
             workspace_data,
+            source_globs,
+            scripts,
+            bins,
+            engines,
+            license,
+            repository,
+            integrity,
+            resolved,
+            overrides,
+            positions,
         }))
     }
 
@@ -106,7 +214,7 @@ impl PackageJsonData {
 
     pub fn get_node_modules(&self) -> Result<Option<Rc<NodeModules>>> {
         self.get_node_modules_path()
-            .map(|path| NodeModules::from_folder(path))
+            .map(NodeModules::from_folder)
             .transpose()
     }
 
@@ -124,15 +232,25 @@ impl PackageJsonData {
     pub fn is_workspace_root(&self) -> bool {
         self.workspace_data.is_some()
     }
+
+    /// Returns true if the package lives outside of any node_modules folder,
+    /// i.e. it is a root package or a workspace member rather than an install.
+    pub fn is_first_party(&self) -> bool {
+        !self
+            .install_path
+            .components()
+            .any(|c| c.as_os_str() == "node_modules")
+    }
 }
 
-pub(crate) fn read_package_json(path: &Path) -> Result<Option<Value>> {
+/// Parses a package.json, along with where each of its keys is
+pub(crate) fn read_package_json(path: &Path) -> Result<Option<(Value, SourcePositions)>> {
     if !path.exists() {
         return Ok(None); // Not a package
     }
     let content = fs::read_to_string(path)?;
     let dep_json: serde_json::Value = serde_json::from_str(&content)?;
-    Ok(Some(dep_json))
+    Ok(Some((dep_json, SourcePositions::scan(&content))))
 }
 
 fn deps_from_value(deps: &serde_json::Value) -> Result<HashMap<String, ExtendedVersionReq>> {
@@ -148,6 +266,126 @@ fn deps_from_value(deps: &serde_json::Value) -> Result<HashMap<String, ExtendedV
     Ok(result)
 }
 
+/// Reads `license`, or the deprecated `licenses` array. Both may hold plain
+/// strings or `{ "type": ... }` objects.
+fn license_from_value(value: &Value) -> Option<String> {
+    let license_type = |v: &Value| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(o) => o.get("type")?.as_str().map(|s| s.to_string()),
+        _ => None,
+    };
+
+    if let Some(license) = value.get("license").and_then(license_type) {
+        return Some(license);
+    }
+
+    let licenses = value
+        .get("licenses")?
+        .as_array()?
+        .iter()
+        .filter_map(license_type)
+        .collect::<Vec<_>>();
+    match licenses.len() {
+        0 => None,
+        1 => licenses.into_iter().next(),
+        _ => Some(format!("({})", licenses.join(" OR "))),
+    }
+}
+
+/// Collects the package names targeted by npm's `overrides`, Yarn and Bun's
+/// `resolutions` and `pnpm.overrides`. Keys may carry a version (`foo@1`),
+/// a path (`**/foo`, `parent/foo`) or a pnpm parent selector (`parent>foo`),
+/// and only the overridden package's name is kept.
+fn overrides_from_value(value: &Value) -> Vec<String> {
+    let sections = [
+        value.get("overrides"),
+        value.get("resolutions"),
+        value.get("pnpm").and_then(|pnpm| pnpm.get("overrides")),
+    ];
+
+    let mut names = Vec::new();
+    for key in sections
+        .into_iter()
+        .flatten()
+        .filter_map(|section| section.as_object())
+        .flat_map(|section| section.keys())
+    {
+        let key = key.rsplit('>').next().unwrap_or(key);
+        let segments = key.split('/').collect::<Vec<_>>();
+        let name = match segments.as_slice() {
+            [.., scope, name] if scope.starts_with('@') => format!("{}/{}", scope, name),
+            [.., name] => name.to_string(),
+            [] => continue,
+        };
+        // Drop a version, but not the `@` of a scope
+        let name = match name.char_indices().skip(1).find(|(_, c)| *c == '@') {
+            Some((at, _)) => name[..at].to_string(),
+            None => name,
+        };
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Reads `repository`, which is either a URL or shorthand string or a
+/// `{ "url": ..., "directory": ... }` object, and normalizes it so that
+/// different spellings of the same repository compare equal.
+fn repository_from_value(value: &Value) -> Option<String> {
+    let repository = value.get("repository")?;
+    let (url, directory) = match repository {
+        Value::String(url) => (url.as_str(), None),
+        Value::Object(o) => (
+            o.get("url")?.as_str()?,
+            o.get("directory").and_then(|d| d.as_str()),
+        ),
+        _ => return None,
+    };
+
+    let mut url = url.trim().to_lowercase();
+    if let Some(path) = url.strip_prefix("github:") {
+        url = format!("github.com/{}", path);
+    } else if let Some((_, rest)) = url.split_once("://") {
+        // Drop the scheme and any credentials
+        url = rest.rsplit_once('@').map_or(rest, |(_, r)| r).to_string();
+    } else if let Some(rest) = url.strip_prefix("git@") {
+        url = rest.replacen(':', "/", 1);
+    } else if !url.contains(':') && url.matches('/').count() == 1 {
+        // `user/repo` shorthand for GitHub
+        url = format!("github.com/{}", url);
+    }
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+
+    Some(match directory {
+        Some(directory) => format!("{}/{}", url, directory.trim_matches('/')),
+        None => url.to_string(),
+    })
+}
+
+fn string_array_from_value(value: &Value, field: &str, install_path: &Path) -> Result<Vec<String>> {
+    value
+        .as_array()
+        .ok_or_else(|| {
+            eyre!(
+                "{} in package.json in {} is not an array",
+                field,
+                install_path.display()
+            )
+        })?
+        .iter()
+        .map(|v| {
+            v.as_str().map(|s| s.to_string()).ok_or_else(|| {
+                eyre!(
+                    "{} entry in package.json in {} is not a string",
+                    field,
+                    install_path.display()
+                )
+            })
+        })
+        .collect()
+}
+
 pub fn get_workspace_globs(value: &Value, install_path: &Path) -> Result<Option<Vec<String>>> {
     Ok(value
         .get("workspaces")
@@ -162,12 +400,12 @@ pub fn get_workspace_globs(value: &Value, install_path: &Path) -> Result<Option<
                     })?
                     .iter()
                     .map(|w| {
-                        Ok(w.as_str().ok_or_else(|| {
+                        w.as_str().ok_or_else(|| {
                             eyre!(
                                 "workspace entry in package.json in {} is not a string",
                                 install_path.display()
                             )
-                        })?)
+                        })
                     })
                     .collect::<Result<Vec<&str>>>()?
                     .into_iter()
@@ -179,3 +417,21 @@ pub fn get_workspace_globs(value: &Value, install_path: &Path) -> Result<Option<
         .transpose()?
         .and_then(|v| if v.is_empty() { None } else { Some(v) }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_from_value() {
+        let value = serde_json::json!({
+            "overrides": { "foo@1": "2.0.0", "@scope/bar": "1.0.0" },
+            "resolutions": { "**/baz": "1.0.0", "parent/@scope/qux@^1": "1.0.0" },
+            "pnpm": { "overrides": { "parent>foo": "2.0.0", "quux": "3.0.0" } }
+        });
+        assert_eq!(
+            overrides_from_value(&value),
+            vec!["@scope/bar", "foo", "baz", "@scope/qux", "quux"]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use color_eyre::eyre::{Result, eyre};
+use colored::*;
+use serde::Serialize;
+
+use crate::{
+    package::{Package, PackageKey},
+    source_scan::{self, Import},
+};
+
+/// An imported package that isn't declared by the importing package, but
+/// still resolves because it was hoisted into a reachable node_modules folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct Phantom {
+    pub name: String,
+    pub resolved: PackageKey,
+    pub install_path: PathBuf,
+    pub imports: Vec<Import>,
+}
+
+impl fmt::Display for Phantom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{} {}",
+            self.name,
+            "@".bright_black(),
+            self.resolved.version_str().blue(),
+            format!("(hoisted at {})", self.install_path.display()).bright_black()
+        )
+    }
+}
+
+/// Finds the phantom dependencies of a package by scanning its sources for
+/// imports of packages it doesn't declare.
+pub fn find_phantoms(package: &Package) -> Result<Vec<Phantom>> {
+    let root_node_modules = package
+        .resolver()
+        .ok_or(eyre!("Dependency resolver is missing"))?
+        .root_node_modules
+        .clone();
+    let node_modules = match package.data.get_node_modules_path() {
+        Some(path) => root_node_modules.get_from_path(&path)?,
+        None => root_node_modules,
+    };
+
+    let mut phantoms: BTreeMap<String, Phantom> = BTreeMap::new();
+    for import in source_scan::scan_package(&package.data)? {
+        let Some(name) = import.package_name() else {
+            continue;
+        };
+        if name == package.name
+            || package.dependencies.contains_key(name)
+            || package.dev_dependencies.contains_key(name)
+            || package.data.peer_dependencies.contains_key(name)
+        {
+            continue;
+        }
+
+        // Undeclared imports that don't resolve at all are broken, not phantom
+        let Some(data) = node_modules.get_package(name) else {
+            continue;
+        };
+
+        phantoms
+            .entry(name.to_string())
+            .or_insert_with(|| Phantom {
+                name: name.to_string(),
+                resolved: PackageKey::from(data.as_ref()),
+                install_path: data.install_path.clone(),
+                imports: Vec::new(),
+            })
+            .imports
+            .push(import);
+    }
+
+    Ok(phantoms.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_find_phantoms() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let package = resolver.resolve(Path::new("tests/phantoms")).unwrap();
+
+        let phantoms = find_phantoms(&package).unwrap();
+        assert_eq!(phantoms.len(), 1);
+        assert_eq!(phantoms[0].name, "y");
+        assert_eq!(phantoms[0].resolved.version_str(), "1.2.0");
+
+        // `host` is only a peer dependency, which still counts as declared.
+        // The import after the regex literals is found, and the symlinked
+        // folder pointing back at src isn't scanned a second time
+        let imports = phantoms[0]
+            .imports
+            .iter()
+            .map(|import| {
+                let file = import
+                    .file
+                    .strip_prefix(&package.data.install_path)
+                    .unwrap();
+                format!("{}:{}", file.display(), import.line)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(imports, vec!["src/index.js:7", "src/nested/util.js:1"]);
+    }
+}
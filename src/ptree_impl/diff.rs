@@ -1,32 +1,51 @@
-use std::{borrow::Cow, cell::OnceCell, fmt, io, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::OnceCell,
+    fmt, io,
+    rc::{Rc, Weak},
+};
 
 use color_eyre::eyre::Result;
 use colored::*;
 use ptree::{Style, TreeItem};
 
 use crate::diff::{
-    ChangedPackageEntry, DiffedDependency, DiffedPackage, DiffedPackageAndVersionReq,
+    ChangedPackageEntry, DiffedDependency, DiffedPackage, DiffedPackageAndVersionReq, Differ,
 };
 
-use super::{ChildOrDevDependencySeparator, ShouldDisplay, Visiting, sorted_values};
+use super::{
+    ChildOrDevDependencySeparator, ShouldDisplay, Visiting, displayed_children, sorted_values,
+};
 
 #[derive(Debug, Clone)]
 pub struct DiffedDepWithPackage {
     dependency: DiffedDependency,
     package: Option<Rc<DiffedPackage>>,
+    differ: Weak<Differ>,
+    // The root package's dependencies are at depth 1
+    depth: usize,
     children: OnceCell<Vec<ChildOrDevDependencySeparator<DiffedDepWithPackage>>>,
 }
 
 impl DiffedDepWithPackage {
-    fn get_children(&self) -> Cow<[ChildOrDevDependencySeparator<DiffedDepWithPackage>]> {
+    fn get_children(&self) -> Cow<'_, [ChildOrDevDependencySeparator<DiffedDepWithPackage>]> {
         self.children
             .get_or_init(|| match &self.package {
-                Some(package) => package.get_children().to_vec(),
+                Some(package) => package.get_children(self.depth + 1).to_vec(),
                 None => vec![],
             })
             .into()
     }
 
+    /// Whether --max-depth hides this dependency's children
+    fn children_cut(&self) -> bool {
+        let differ = self.differ.upgrade().expect("Differ is missing");
+        differ
+            .filter
+            .max_depth
+            .is_some_and(|max_depth| self.depth >= max_depth)
+    }
+
     fn visited(&self) -> bool {
         match &self.package {
             Some(package) => *package.visited.borrow(),
@@ -37,7 +56,7 @@ impl DiffedDepWithPackage {
 
 impl fmt::Display for DiffedDepWithPackage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let deduped_str = if self.package.as_ref().map_or(false, |p| *p.visited.borrow()) {
+        let deduped_str = if self.package.as_ref().is_some_and(|p| *p.visited.borrow()) {
             " [DEDUPED]".bright_black()
         } else {
             "".into()
@@ -49,13 +68,23 @@ impl fmt::Display for DiffedDepWithPackage {
 
 impl ShouldDisplay for DiffedDepWithPackage {
     fn should_display(&self) -> bool {
+        let differ = self.differ.upgrade().expect("Differ is missing");
+        let filter = &differ.filter;
+        // Dependencies that aren't included are still shown as the path to an
+        // included change further down
+        let included = filter.includes(&self.dependency);
+
         match &self.dependency.package {
             DiffedPackageAndVersionReq::Changed {
                 package: ChangedPackageEntry::Resolved(key),
                 ..
             } => {
-                key.left.name != key.right.name
-                    || key.left.version != key.right.version
+                (included
+                    && (key.left.name != key.right.name || self.dependency.renamed_from.is_some())
+                    && filter.only.is_empty())
+                    || (included
+                        && key.left.version != key.right.version
+                        && filter.matches_bump(key.bump()))
                     || match &self.package {
                         Some(package) => {
                             if self.visited() {
@@ -79,7 +108,11 @@ impl ShouldDisplay for DiffedDepWithPackage {
                         None => false,
                     }
             }
-            _ => true,
+            DiffedPackageAndVersionReq::Changed { package, .. } => {
+                included && filter.matches(package.kind())
+            }
+            // Added and removed dependencies aren't version changes
+            _ => included && filter.only.is_empty(),
         }
     }
 }
@@ -100,20 +133,18 @@ impl TreeItem for DiffedDepWithPackage {
         write!(f, "{}", style.paint(self))
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
+    fn children(&self) -> Cow<'_, [Self::Child]> {
         if let Some(package) = &self.package {
             if *package.visited.borrow() {
                 return Cow::Borrowed(&[]);
-            } else {
+            } else if !self.children_cut() {
+                // A copy whose children are cut by --max-depth isn't marked,
+                // so a shallower copy still shows them
                 *package.visited.borrow_mut() = true;
             }
         }
 
-        self.get_children()
-            .into_iter()
-            .cloned()
-            .filter(|c| c.should_display())
-            .collect()
+        displayed_children(&self.get_children()).into()
     }
 }
 
@@ -121,44 +152,63 @@ impl DiffedPackage {
     fn populate_children<I: IntoIterator<Item = DiffedDependency>>(
         &self,
         deps: I,
+        depth: usize,
     ) -> Result<Vec<DiffedDepWithPackage>> {
+        let differ = self.differ().expect("Failed to get differ");
+
         deps.into_iter()
+            .filter(|d| !differ.filter.excludes(d))
             .map(|d| {
                 let package = match &d.package {
                     DiffedPackageAndVersionReq::Changed {
                         package: ChangedPackageEntry::Resolved(key),
                         ..
-                    } => self
-                        .differ()
-                        .expect("Failed to get differ")
-                        .get_package(&key),
+                    } => differ.get_package(key),
                     _ => None,
                 };
 
                 Ok(DiffedDepWithPackage {
                     dependency: d.clone(),
                     package,
+                    differ: self.differ.clone(),
+                    depth,
                     children: OnceCell::new(),
                 })
             })
             .collect()
     }
 
-    fn get_children(&self) -> Cow<[ChildOrDevDependencySeparator<DiffedDepWithPackage>]> {
-        let mut v: Vec<ChildOrDevDependencySeparator<DiffedDepWithPackage>> = self
-            .populate_children(sorted_values(&self.dependencies))
-            .expect("Failed to populate children")
-            .into_iter()
-            .map(|r| ChildOrDevDependencySeparator::Child(r))
-            .collect();
+    /// Returns the dependencies at `depth` that pass the diff filter's name,
+    /// kind and depth restrictions. Whether they changed is left to `ShouldDisplay`.
+    fn get_children(
+        &self,
+        depth: usize,
+    ) -> Cow<'_, [ChildOrDevDependencySeparator<DiffedDepWithPackage>]> {
+        let differ = self.differ().expect("Failed to get differ");
+        let filter = &differ.filter;
+        if filter.max_depth.is_some_and(|max_depth| depth > max_depth) {
+            return Cow::Borrowed(&[]);
+        }
+
+        // This package is one level above its children
+        let mut v: Vec<ChildOrDevDependencySeparator<DiffedDepWithPackage>> =
+            if filter.shows_dependencies(depth - 1, false) {
+                self.populate_children(sorted_values(&self.dependencies), depth)
+                    .expect("Failed to populate children")
+                    .into_iter()
+                    .map(ChildOrDevDependencySeparator::Child)
+                    .collect()
+            } else {
+                Vec::new()
+            };
 
-        if !self.dev_dependencies.is_empty() {
+        if !self.dev_dependencies.is_empty() && filter.shows_dependencies(depth - 1, true) {
             v.push(ChildOrDevDependencySeparator::DevDependencySeparator);
             v.extend(
-                self.populate_children(sorted_values(&self.dev_dependencies))
+                self.populate_children(sorted_values(&self.dev_dependencies), depth)
                     .expect("Failed to populate children")
                     .into_iter()
-                    .map(|r| ChildOrDevDependencySeparator::Child(r)),
+                    .map(ChildOrDevDependencySeparator::Child),
             );
         }
 
@@ -173,17 +223,13 @@ impl TreeItem for DiffedPackage {
         write!(f, "{}", style.paint(self))
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
+    fn children(&self) -> Cow<'_, [Self::Child]> {
         if *self.visited.borrow() {
             return Cow::Borrowed(&[]);
         } else {
             *self.visited.borrow_mut() = true;
         }
 
-        self.get_children()
-            .into_iter()
-            .cloned()
-            .filter(|c| c.should_display())
-            .collect()
+        displayed_children(&self.get_children(1)).into()
     }
 }
@@ -47,7 +47,7 @@ impl<C: TreeItem> TreeItem for ChildOrDevDependencySeparator<C> {
         }
     }
 
-    fn children(&self) -> Cow<[C::Child]> {
+    fn children(&self) -> Cow<'_, [C::Child]> {
         if let Self::Child(child) = self {
             child.children()
         } else {
@@ -74,6 +74,22 @@ impl<C: TreeItem + Visiting> Visiting for ChildOrDevDependencySeparator<C> {
     }
 }
 
+/// Keeps the children that should be displayed, dropping the dev dependency
+/// separator if no dev dependency is left after it
+pub(crate) fn displayed_children<C: TreeItem + ShouldDisplay>(
+    children: &[ChildOrDevDependencySeparator<C>],
+) -> Vec<ChildOrDevDependencySeparator<C>> {
+    let mut displayed = children
+        .iter()
+        .filter(|&c| c.should_display())
+        .cloned()
+        .collect::<Vec<_>>();
+    if let Some(ChildOrDevDependencySeparator::DevDependencySeparator) = displayed.last() {
+        displayed.pop();
+    }
+    displayed
+}
+
 pub fn sorted_values<K: Ord + Clone, V: Clone>(deps: &HashMap<K, V>) -> Vec<V> {
     let mut values = deps
         .iter()
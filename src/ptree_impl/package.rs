@@ -15,13 +15,17 @@ pub struct DepWithPackage {
 
 impl fmt::Display for DepWithPackage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let deduped_str = if self.package.as_ref().map_or(false, |p| *p.visited.borrow()) {
+        let deduped_str = if self.package.as_ref().is_some_and(|p| *p.visited.borrow()) {
             " [DEDUPED]".bright_black()
         } else {
             "".into()
         };
+        let deprecated_str = self
+            .package
+            .as_ref()
+            .map_or("".into(), |p| p.deprecated_badge());
 
-        write!(f, "{}{}", self.dependency, deduped_str)
+        write!(f, "{}{}{}", self.dependency, deprecated_str, deduped_str)
     }
 }
 
@@ -32,7 +36,7 @@ impl TreeItem for DepWithPackage {
         write!(f, "{}", style.paint(self))
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
+    fn children(&self) -> Cow<'_, [Self::Child]> {
         if let Some(package) = &self.package {
             Cow::from(package.children().to_vec())
         } else {
@@ -50,7 +54,7 @@ impl Package {
             .map(|d| {
                 let package = match &d.package {
                     PackageEntry::Resolved(key) => {
-                        self.dep_resolver.upgrade().unwrap().get_package(&key)
+                        self.dep_resolver.upgrade().unwrap().get_package(key)
                     }
                     _ => None,
                 };
@@ -71,7 +75,7 @@ impl TreeItem for Package {
         write!(f, "{}", style.paint(self))
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
+    fn children(&self) -> Cow<'_, [Self::Child]> {
         if *self.visited.borrow() {
             return Cow::Borrowed(&[]);
         } else {
@@ -82,16 +86,16 @@ impl TreeItem for Package {
             .populate_children(sorted_values(&self.dependencies))
             .expect("Failed to populate children")
             .into_iter()
-            .map(|d| ChildOrDevDependencySeparator::Child(d))
+            .map(ChildOrDevDependencySeparator::Child)
             .collect();
 
         if !self.dev_dependencies.is_empty() {
             v.push(ChildOrDevDependencySeparator::DevDependencySeparator);
             v.extend(
-                self.populate_children(self.dev_dependencies.values().cloned())
+                self.populate_children(sorted_values(&self.dev_dependencies))
                     .expect("Failed to populate children")
                     .into_iter()
-                    .map(|d| ChildOrDevDependencySeparator::Child(d)),
+                    .map(ChildOrDevDependencySeparator::Child),
             );
         }
 
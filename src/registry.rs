@@ -0,0 +1,314 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use color_eyre::eyre::{Result, eyre};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::debug;
+
+/// The prefix npm uses for HTTP responses in its cache index
+const CACACHE_KEY_PREFIX: &str = "make-fetch-happen:request-cache:";
+
+/// How long a request to a registry mirror may take, including connecting
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Registry metadata for a package, as served by `GET /<name>`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Packument {
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    pub versions: HashMap<String, PackumentVersion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackumentVersion {
+    // A string message, or `false` when a deprecation was lifted
+    #[serde(default)]
+    deprecated: Option<Value>,
+}
+
+impl PackumentVersion {
+    pub fn deprecation(&self) -> Option<&str> {
+        self.deprecated
+            .as_ref()
+            .and_then(|d| d.as_str())
+            .filter(|message| !message.is_empty())
+    }
+}
+
+#[derive(Debug)]
+enum RegistrySource {
+    /// A folder of `<name>.json` packuments (scoped ones in `@scope/name.json`)
+    Folder(PathBuf),
+    /// npm's `_cacache` folder, with the content path of each cached packument
+    Cacache(HashMap<String, PathBuf>),
+    /// A registry served over HTTP, such as a local verdaccio instance
+    Http { url: String, agent: ureq::Agent },
+}
+
+/// Read-only access to packuments stored on disk or served by a registry mirror
+#[derive(Debug)]
+pub struct Registry {
+    source: RegistrySource,
+    packuments: RefCell<HashMap<String, Option<Rc<Packument>>>>,
+}
+
+impl Registry {
+    /// Opens an `http://` registry URL, npm's `_cacache` folder (recognized by
+    /// its `index-v5` folder) or a plain folder of packuments.
+    pub fn open(location: &str) -> Result<Self> {
+        // Mirrors are expected to be local, so the HTTP client is built without TLS
+        if location.starts_with("https://") {
+            return Err(eyre!(
+                "Registry {} uses https, which isn't supported; use an http:// mirror or a folder of cached packuments",
+                location
+            ));
+        }
+        if location.starts_with("http://") {
+            return Ok(Self {
+                source: RegistrySource::Http {
+                    url: location.trim_end_matches('/').to_string(),
+                    agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+                },
+                packuments: RefCell::new(HashMap::new()),
+            });
+        }
+
+        let path = Path::new(location);
+        if !path.is_dir() {
+            return Err(eyre!("Registry folder {} does not exist", path.display()));
+        }
+
+        let source = if path.join("index-v5").is_dir() {
+            RegistrySource::Cacache(read_cacache_index(path)?)
+        } else {
+            RegistrySource::Folder(path.to_path_buf())
+        };
+
+        Ok(Self {
+            source,
+            packuments: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the packument for a package, or None if the registry doesn't have it
+    pub fn packument(&self, name: &str) -> Result<Option<Rc<Packument>>> {
+        if let Some(packument) = self.packuments.borrow().get(name) {
+            return Ok(packument.clone());
+        }
+
+        let path = match &self.source {
+            RegistrySource::Folder(folder) => [
+                folder.join(format!("{}.json", name)),
+                folder.join(format!("{}.json", name.replace('/', "%2f"))),
+            ]
+            .into_iter()
+            .find(|path| path.exists()),
+            RegistrySource::Cacache(index) => index.get(name).cloned(),
+            RegistrySource::Http { .. } => None,
+        };
+
+        let packument = match (&self.source, path) {
+            (RegistrySource::Http { url, agent }, _) => {
+                fetch_packument(agent, url, name)?.map(Rc::new)
+            }
+            (_, Some(path)) => {
+                debug!("Reading packument for {} from {}", name, path.display());
+                let content = fs::read_to_string(&path)?;
+                let packument = serde_json::from_str(&content).map_err(|e| {
+                    eyre!(
+                        "Invalid packument for {} in {}: {}",
+                        name,
+                        path.display(),
+                        e
+                    )
+                })?;
+                Some(Rc::new(packument))
+            }
+            (_, None) => None,
+        };
+
+        self.packuments
+            .borrow_mut()
+            .insert(name.to_string(), packument.clone());
+        Ok(packument)
+    }
+}
+
+/// Requests the abbreviated packument, which still has the dist-tags and
+/// deprecation messages. Packages the registry doesn't know return None.
+fn fetch_packument(
+    agent: &ureq::Agent,
+    registry_url: &str,
+    name: &str,
+) -> Result<Option<Packument>> {
+    let url = format!("{}/{}", registry_url, name.replace('/', "%2f"));
+    debug!("Fetching packument for {} from {}", name, url);
+
+    let response = match agent
+        .get(&url)
+        .set("Accept", "application/vnd.npm.install-v1+json")
+        .call()
+    {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(eyre!("Unable to fetch {}: {}", url, e)),
+    };
+
+    let packument = serde_json::from_reader(response.into_reader())
+        .map_err(|e| eyre!("Invalid packument for {} from {}: {}", name, url, e))?;
+    Ok(Some(packument))
+}
+
+/// Maps package names to cached packument contents. Index files hold lines of
+/// `<hash>\t<json entry>`, where the entry's integrity locates the content.
+fn read_cacache_index(cacache: &Path) -> Result<HashMap<String, PathBuf>> {
+    let mut index = HashMap::new();
+    let pattern = cacache.join("index-v5").join("**").join("*");
+
+    for path in glob::glob(&pattern.to_string_lossy())?.flatten() {
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for line in content.lines() {
+            let Some((_, entry)) = line.split_once('\t') else {
+                continue;
+            };
+            let Ok(entry) = serde_json::from_str::<Value>(entry) else {
+                continue;
+            };
+            let Some(name) = entry
+                .get("key")
+                .and_then(|k| k.as_str())
+                .and_then(|k| k.strip_prefix(CACACHE_KEY_PREFIX))
+                .and_then(package_name_from_url)
+            else {
+                continue;
+            };
+            let Some(content_path) = entry
+                .get("integrity")
+                .and_then(|i| i.as_str())
+                .and_then(|i| content_path(cacache, i))
+            else {
+                continue;
+            };
+            // Later lines are newer entries for the same key
+            index.insert(name, content_path);
+        }
+    }
+
+    Ok(index)
+}
+
+/// Extracts the package name from a packument URL such as
+/// `https://registry.npmjs.org/@scope%2fname`. Tarball URLs return None.
+fn package_name_from_url(url: &str) -> Option<String> {
+    let path = url.split_once("://")?.1.split_once('/')?.1;
+    if path.is_empty() || path.contains("/-/") {
+        return None;
+    }
+    Some(
+        path.replace("%2f", "/")
+            .replace("%2F", "/")
+            .replace("%40", "@"),
+    )
+}
+
+fn content_path(cacache: &Path, integrity: &str) -> Option<PathBuf> {
+    let (algorithm, digest) = integrity.split_whitespace().next()?.split_once('-')?;
+    let hex = BASE64
+        .decode(digest)
+        .ok()?
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    if hex.len() < 4 {
+        return None;
+    }
+    Some(
+        cacache
+            .join("content-v2")
+            .join(algorithm)
+            .join(&hex[..2])
+            .join(&hex[2..4])
+            .join(&hex[4..]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_name_from_url() {
+        assert_eq!(
+            package_name_from_url("https://registry.npmjs.org/react").as_deref(),
+            Some("react")
+        );
+        assert_eq!(
+            package_name_from_url("https://registry.npmjs.org/@types%2freact").as_deref(),
+            Some("@types/react")
+        );
+        assert_eq!(
+            package_name_from_url("https://registry.npmjs.org/react/-/react-18.2.0.tgz"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_https() {
+        let error = Registry::open("https://registry.npmjs.org").unwrap_err();
+        assert!(error.to_string().contains("https"));
+        assert!(Registry::open("http://localhost:4873/").is_ok());
+    }
+
+    #[test]
+    fn test_content_path() {
+        let path = content_path(Path::new("/cache"), "sha1-qUnZb0v7k5m+EVbB1fg5q8Yi3Dk=").unwrap();
+        assert_eq!(
+            path,
+            Path::new("/cache/content-v2/sha1/a9/49/d96f4bfb9399be1156c1d5f839abc622dc39")
+        );
+        // A digest too short to split into folders is ignored rather than panicking
+        assert_eq!(content_path(Path::new("/cache"), "sha1-qQ=="), None);
+    }
+
+    #[test]
+    fn test_packument_lookup() {
+        let folder = Registry::open("tests/registry/packuments").unwrap();
+        let old = folder.packument("old").unwrap().unwrap();
+        assert_eq!(old.dist_tags["latest"], "2.1.0");
+        assert_eq!(
+            old.versions["1.0.0"].deprecation(),
+            Some("Use old@2 instead")
+        );
+        let gone = folder.packument("@scope/gone").unwrap().unwrap();
+        assert_eq!(
+            gone.versions["1.0.0"].deprecation(),
+            Some("No longer maintained")
+        );
+        let lifted = folder.packument("lifted").unwrap().unwrap();
+        assert_eq!(lifted.versions["1.0.0"].deprecation(), None);
+        assert!(folder.packument("unknown").unwrap().is_none());
+
+        // The newest index entry for a packument wins, and cached tarballs are skipped
+        let cacache = Registry::open("tests/registry/_cacache").unwrap();
+        let gone = cacache.packument("@scope/gone").unwrap().unwrap();
+        assert_eq!(
+            gone.versions["1.0.0"].deprecation(),
+            Some("No longer maintained")
+        );
+        assert!(cacache.packument("old").unwrap().is_none());
+    }
+}
@@ -41,7 +41,7 @@ impl Resolver {
         let package_path = path.canonicalize()?;
         let mut current_path = package_path.clone();
         loop {
-            if let Some(value) = read_package_json(&current_path.join("package.json"))? {
+            if let Some((value, _)) = read_package_json(&current_path.join("package.json"))? {
                 debug!("Checking if {} is a workspace root", current_path.display());
                 if self.workspace_roots.contains_key(&current_path) {
                     return Ok(Some(self.workspace_roots[&current_path].clone()));
@@ -49,7 +49,7 @@ impl Resolver {
                     if let Some(workspaces_globs) = get_workspace_globs(&value, &current_path)? {
                         if package_path != current_path {
                             let globset = build_globset_from_globs(&workspaces_globs)?;
-                            if !globset.is_match(&norm_for_glob(&package_path.to_string_lossy())) {
+                            if !globset.is_match(norm_for_glob(&package_path.to_string_lossy())) {
                                 continue;
                             }
                         }
@@ -140,7 +140,7 @@ impl Resolver {
                     .root_node_modules
                     .get_from_path(&package_data.install_path)?;
 
-                let entry = dep_resolver.resolve_package(&package_data, &node_modules)?;
+                let entry = dep_resolver.resolve_package(package_data, &node_modules)?;
                 let package = dep_resolver.unwrap_entry(entry)?;
                 Ok(package)
             })
@@ -153,7 +153,7 @@ fn build_globset_from_globs<T: AsRef<str>>(globs: &[T]) -> Result<GlobSet> {
     for glob in globs {
         builder.add(Glob::new(&norm_for_glob(glob.as_ref()))?);
     }
-    return Ok(builder.build()?);
+    Ok(builder.build()?)
 }
 
 fn norm_for_glob(path: &str) -> String {
@@ -174,7 +174,7 @@ mod tests {
     fn test_globset() {
         let globs = vec!["/packages/*"];
         let globset = build_globset_from_globs(&globs).unwrap();
-        assert!(globset.is_match(&norm_for_glob("/packages/react-vite")));
-        assert!(globset.is_match(&norm_for_glob("/packages/react-vite/node_modules")));
+        assert!(globset.is_match(norm_for_glob("/packages/react-vite")));
+        assert!(globset.is_match(norm_for_glob("/packages/react-vite/node_modules")));
     }
 }
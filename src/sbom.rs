@@ -0,0 +1,307 @@
+use std::{collections::HashMap, rc::Rc};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use lazy_static::lazy_static;
+
+use crate::{
+    graph::DependencyGraph,
+    licenses::{self, LicenseInfo, SpdxExpression},
+    lockfile,
+    package::{Package, PackageEntry, PackageKey},
+    ptree_impl::sorted_values,
+};
+
+/// A tarball digest, decoded from a Subresource Integrity string
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checksum {
+    /// The lowercase algorithm name, e.g. `sha512`
+    pub algorithm: String,
+    pub hex: String,
+}
+
+/// A package as it appears in a software bill of materials
+#[derive(Debug)]
+pub struct Component {
+    pub package: Rc<Package>,
+    pub purl: String,
+    pub license: LicenseInfo,
+    pub hashes: Vec<Checksum>,
+    /// Root packages and workspace members
+    pub first_party: bool,
+    /// Only reachable through devDependencies
+    pub dev_only: bool,
+    /// The purls of the components this one depends on, and whether each is a dev dependency
+    pub dependencies: Vec<(String, bool)>,
+}
+
+/// Collects one component per distinct purl in the graph, roots first.
+/// `production` is the graph without dev dependencies, used to tell which
+/// packages are dev-only.
+pub fn collect_components(graph: &DependencyGraph, production: &DependencyGraph) -> Vec<Component> {
+    let mut locked = HashMap::new();
+    for package in graph.packages() {
+        let key = PackageKey::from(package.as_ref());
+        if graph.depth(&key) == Some(0) {
+            locked.extend(lockfile::read_locked_packages(&package.data.install_path));
+        }
+    }
+
+    let mut components: Vec<Component> = Vec::new();
+    let mut indices = HashMap::new();
+    for package in graph.packages() {
+        let key = PackageKey::from(package.as_ref());
+        let purl = purl(
+            &package.name,
+            package.version.as_ref().map(|v| v.to_string()),
+        );
+
+        let mut dependencies = Vec::new();
+        for (dependencies_of_kind, dev) in [
+            (&package.dependencies, false),
+            (&package.dev_dependencies, true),
+        ] {
+            for dependency in sorted_values(dependencies_of_kind) {
+                if let PackageEntry::Resolved(child_key) = &dependency.package
+                    && graph.get(child_key).is_some()
+                {
+                    dependencies.push((key_purl(child_key), dev));
+                }
+            }
+        }
+
+        // Copies of the same version installed in several places share a purl
+        if let Some(&index) = indices.get(&purl) {
+            let component: &mut Component = &mut components[index];
+            component.dev_only &= production.get(&key).is_none();
+            for dependency in dependencies {
+                if !component.dependencies.contains(&dependency) {
+                    component.dependencies.push(dependency);
+                }
+            }
+            continue;
+        }
+
+        let integrity = package.data.integrity.as_deref().or_else(|| {
+            locked
+                .get(&package.data.install_path)
+                .and_then(|locked| locked.integrity.as_deref())
+        });
+        indices.insert(purl.clone(), components.len());
+        components.push(Component {
+            package: package.clone(),
+            purl,
+            license: licenses::package_license(&package.data),
+            hashes: integrity.map(checksums).unwrap_or_default(),
+            first_party: package.data.is_first_party(),
+            dev_only: production.get(&key).is_none(),
+            dependencies,
+        });
+    }
+
+    components
+}
+
+lazy_static! {
+    /// The ids on the SPDX license list, keyed by their lowercase spelling
+    static ref LICENSE_IDS: HashMap<String, &'static str> = id_list(include_str!("spdx-license-ids.txt"));
+    static ref EXCEPTION_IDS: HashMap<String, &'static str> = id_list(include_str!("spdx-exceptions.txt"));
+}
+
+fn id_list(list: &'static str) -> HashMap<String, &'static str> {
+    list.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|id| (id.to_lowercase(), id))
+        .collect()
+}
+
+/// A declared license written as a valid SPDX expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpdxLicense {
+    pub expression: String,
+    /// The `LicenseRef-` ids standing in for ids that aren't on the SPDX
+    /// license list, with the ids as declared
+    pub references: Vec<(String, String)>,
+}
+
+impl Component {
+    /// The license as an SPDX expression, or None for values such as
+    /// `UNLICENSED` or `SEE LICENSE IN LICENSE.txt` that aren't expressions.
+    /// License ids are spelled as on the SPDX license list, and ids that
+    /// aren't on it become `LicenseRef-` ids. Unknown exceptions can't be
+    /// referenced that way, so they make the whole license None.
+    pub fn spdx_license(&self) -> Option<SpdxLicense> {
+        spdx_license(self.license.expression.as_deref()?)
+    }
+}
+
+/// Rewrites a declared license as described for [`Component::spdx_license`]
+fn spdx_license(expression: &str) -> Option<SpdxLicense> {
+    if let SpdxExpression::License(id) = SpdxExpression::parse(expression)
+        && (id.contains(char::is_whitespace) || id == "UNLICENSED")
+    {
+        return None;
+    }
+
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+    let mut tokens = Vec::new();
+    let mut references = Vec::new();
+    let mut exception = false;
+    for token in spaced.split_whitespace() {
+        let upper = token.to_uppercase();
+        let token = match upper.as_str() {
+            "(" | ")" => upper,
+            "AND" | "OR" | "WITH" => {
+                exception = upper == "WITH";
+                tokens.push(upper);
+                continue;
+            }
+            _ if exception => EXCEPTION_IDS.get(&token.to_lowercase())?.to_string(),
+            _ => {
+                let (id, plus) = match token.strip_suffix('+') {
+                    Some(id) => (id, "+"),
+                    None => (token, ""),
+                };
+                if let Some(id) = LICENSE_IDS.get(&id.to_lowercase()) {
+                    format!("{}{}", id, plus)
+                } else {
+                    let reference = match token.get(..11) {
+                        Some(prefix) if prefix.eq_ignore_ascii_case("LicenseRef-") => {
+                            format!("LicenseRef-{}", sanitize_license_ref(&token[11..]))
+                        }
+                        _ => format!("LicenseRef-{}", sanitize_license_ref(token)),
+                    };
+                    if !references.iter().any(|(r, _)| *r == reference) {
+                        references.push((reference.clone(), token.to_string()));
+                    }
+                    reference
+                }
+            }
+        };
+        exception = false;
+        tokens.push(token);
+    }
+
+    Some(SpdxLicense {
+        expression: tokens.join(" ").replace("( ", "(").replace(" )", ")"),
+        references,
+    })
+}
+
+/// LicenseRef- ids may only contain letters, digits, `.` and `-`
+fn sanitize_license_ref(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// The package URL of an npm package, e.g. `pkg:npm/%40scope/name@1.0.0`
+pub fn purl(name: &str, version: Option<String>) -> String {
+    let path = match name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+        Some((scope, name)) => format!("%40{}/{}", encode(scope), encode(name)),
+        None => encode(name),
+    };
+    match version {
+        Some(version) => format!("pkg:npm/{}@{}", path, encode(&version)),
+        None => format!("pkg:npm/{}", path),
+    }
+}
+
+fn key_purl(key: &PackageKey) -> String {
+    purl(&key.name, key.version.as_ref().map(|v| v.to_string()))
+}
+
+/// Percent-encodes everything but the characters purl segments may contain as is
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Decodes an integrity string such as `sha512-<base64>`, which may list
+/// several space separated digests
+pub fn checksums(integrity: &str) -> Vec<Checksum> {
+    integrity
+        .split_whitespace()
+        .filter_map(|digest| {
+            let (algorithm, digest) = digest.split_once('-')?;
+            // Options such as `?foo` may follow the digest
+            let digest = digest.split('?').next()?;
+            let hex = BASE64
+                .decode(digest)
+                .ok()?
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+            Some(Checksum {
+                algorithm: algorithm.to_lowercase(),
+                hex,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spdx_license() {
+        let expression = |declared| spdx_license(declared).map(|license| license.expression);
+        assert_eq!(expression("mit").as_deref(), Some("MIT"));
+        assert_eq!(expression("gpl-2.0+").as_deref(), Some("GPL-2.0+"));
+        assert_eq!(
+            expression("(apache-2.0 or mit) and bsd-3-clause").as_deref(),
+            Some("(Apache-2.0 OR MIT) AND BSD-3-Clause")
+        );
+        assert_eq!(
+            expression("GPL-2.0-only with classpath-exception-2.0").as_deref(),
+            Some("GPL-2.0-only WITH Classpath-exception-2.0")
+        );
+        assert_eq!(expression("GPL-2.0-only WITH Made-Up-exception"), None);
+        assert_eq!(expression("UNLICENSED"), None);
+        assert_eq!(expression("SEE LICENSE IN LICENSE.txt"), None);
+
+        assert_eq!(
+            spdx_license("MIT OR Acme_1.0 OR LicenseRef-Acme_1.0"),
+            Some(SpdxLicense {
+                expression: "MIT OR LicenseRef-Acme-1.0 OR LicenseRef-Acme-1.0".into(),
+                references: vec![("LicenseRef-Acme-1.0".into(), "Acme_1.0".into())],
+            })
+        );
+    }
+
+    #[test]
+    fn test_purl_and_checksums() {
+        assert_eq!(
+            purl("@types/node", Some("20.1.0+build".to_string())),
+            "pkg:npm/%40types/node@20.1.0%2Bbuild"
+        );
+        assert_eq!(purl("lodash", None), "pkg:npm/lodash");
+
+        assert_eq!(
+            checksums("sha1-AAEC sha512-/w=="),
+            vec![
+                Checksum {
+                    algorithm: "sha1".to_string(),
+                    hex: "000102".to_string(),
+                },
+                Checksum {
+                    algorithm: "sha512".to_string(),
+                    hex: "ff".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::package_data::{PackageJsonData, read_package_json};
+
+/// Globs used when a package doesn't configure `nmvc.sources` in its package.json
+pub const DEFAULT_SOURCE_GLOBS: &[&str] = &["**/*.{js,jsx,ts,tsx,mjs,cjs,mts,cts}"];
+
+/// Build output folders that are skipped unless source globs are configured
+const DEFAULT_IGNORED_DIRS: &[&str] = &["dist", "build", "coverage"];
+
+/// Tool configuration files looked for in the root of a package
+const CONFIG_FILE_GLOBS: &[&str] = &[
+    "*.config.{js,cjs,mjs,ts,cts,mts,json}",
+    ".*rc",
+    ".*rc.{js,cjs,mjs,json,yaml,yml}",
+    "{tsconfig,jsconfig}*.json",
+];
+
+/// package.json fields that hold tool configuration
+const PACKAGE_JSON_CONFIG_FIELDS: &[&str] = &["eslintConfig", "babel", "prettier", "jest"];
+
+/// Keywords after which a `/` starts a regex literal
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+const NODE_BUILTINS: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+lazy_static! {
+    static ref IMPORT_PATTERNS: Vec<Regex> = vec![
+        // import x from "y", export { x } from "y"
+        Regex::new(r#"\bfrom\s*["']([^"'\n]+)["']"#).unwrap(),
+        // import "y"
+        Regex::new(r#"\bimport\s*["']([^"'\n]+)["']"#).unwrap(),
+        // import("y"), require("y"), require.resolve("y")
+        Regex::new(r#"\b(?:import|require|require\.resolve)\s*\(\s*["']([^"'\n]+)["']\s*\)"#)
+            .unwrap(),
+    ];
+    // Triple-slash directives live in comments, so they are matched before stripping them
+    static ref REFERENCE_PATTERN: Regex =
+        Regex::new(r#"///\s*<reference\s+types\s*=\s*["']([^"'\n]+)["']"#).unwrap();
+    static ref STRING_PATTERN: Regex = Regex::new(r#"["'`]([^"'`\n]+)["'`]"#).unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Import {
+    pub specifier: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl Import {
+    /// The package that a bare specifier refers to, see [`package_name`]
+    pub fn package_name(&self) -> Option<&str> {
+        package_name(&self.specifier)
+    }
+}
+
+/// Returns the package name of a bare import specifier, or None if the
+/// specifier is relative, absolute, a Node builtin or uses a protocol.
+pub fn package_name(specifier: &str) -> Option<&str> {
+    if specifier.starts_with('.')
+        || specifier.starts_with('/')
+        || specifier.starts_with('#')
+        || specifier.contains(':')
+        || is_node_builtin(specifier)
+    {
+        return None;
+    }
+
+    let mut segments = specifier.splitn(3, '/');
+    let first = segments.next()?;
+    let end = if let Some(scope) = first.strip_prefix('@') {
+        let name = segments.next()?;
+        if scope.is_empty() || name.is_empty() {
+            // Path aliases such as "@/components"
+            return None;
+        }
+        first.len() + 1 + name.len()
+    } else {
+        first.len()
+    };
+
+    let name = &specifier[..end];
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "@/-._~".contains(c))
+        .then_some(name)
+}
+
+/// Returns true for Node builtins, with or without the `node:` prefix
+pub fn is_node_builtin(specifier: &str) -> bool {
+    if specifier.starts_with("node:") {
+        return true;
+    }
+    let module = specifier.split('/').next().unwrap_or(specifier);
+    NODE_BUILTINS.contains(&module)
+}
+
+/// Finds the source files of a package, skipping node_modules, hidden folders
+/// and nested packages (e.g. workspace members below a workspace root).
+pub fn find_source_files(package: &PackageJsonData) -> Result<Vec<PathBuf>> {
+    let (globs, ignored_dirs) = match &package.source_globs {
+        Some(globs) => (build_globset(globs)?, &[][..]),
+        None => (build_globset(DEFAULT_SOURCE_GLOBS)?, DEFAULT_IGNORED_DIRS),
+    };
+
+    let mut files = Vec::new();
+    walk(
+        &package.install_path,
+        &package.install_path,
+        &globs,
+        ignored_dirs,
+        &mut files,
+    )?;
+    files.sort();
+    Ok(files)
+}
+
+pub(crate) fn build_globset<T: AsRef<str>>(globs: &[T]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob.as_ref())?);
+    }
+    Ok(builder.build()?)
+}
+
+fn walk(
+    root: &Path,
+    dir: &Path,
+    globs: &GlobSet,
+    ignored_dirs: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        // Symlinked folders can point back up the tree, so they aren't followed
+        if entry.file_type().is_ok_and(|t| t.is_symlink()) && path.is_dir() {
+            continue;
+        }
+        if path.is_dir() {
+            if file_name == "node_modules"
+                || file_name.starts_with('.')
+                || ignored_dirs.contains(&file_name.as_str())
+                || path.join("package.json").exists()
+            {
+                continue;
+            }
+            walk(root, &path, globs, ignored_dirs, files)?;
+        } else if path
+            .strip_prefix(root)
+            .is_ok_and(|relative| globs.is_match(relative))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Scans all source files of a package for import specifiers
+pub fn scan_package(package: &PackageJsonData) -> Result<Vec<Import>> {
+    let mut imports = Vec::new();
+    for file in find_source_files(package)? {
+        debug!("Scanning {} for imports", file.display());
+        let Ok(source) = fs::read_to_string(&file) else {
+            continue; // Not UTF-8, so not a source file we can read
+        };
+        imports.extend(
+            scan_imports(&source)
+                .into_iter()
+                .map(|(specifier, line)| Import {
+                    specifier,
+                    file: file.clone(),
+                    line,
+                }),
+        );
+    }
+    Ok(imports)
+}
+
+/// Extracts `import`/`export ... from`/`require`/`import()` specifiers from
+/// JS or TS source, along with the (1-based) line they appear on.
+pub fn scan_imports(source: &str) -> Vec<(String, usize)> {
+    let stripped = strip_comments(source);
+    let with_lines = |text: &str, m: regex::Match| {
+        let line = text[..m.start()].matches('\n').count() + 1;
+        (m.as_str().to_string(), line)
+    };
+
+    let mut imports = IMPORT_PATTERNS
+        .iter()
+        .flat_map(|pattern| pattern.captures_iter(&stripped))
+        .filter_map(|captures| captures.get(1))
+        .map(|m| with_lines(&stripped, m))
+        .chain(
+            REFERENCE_PATTERN
+                .captures_iter(source)
+                .filter_map(|captures| captures.get(1))
+                .map(|m| with_lines(source, m)),
+        )
+        .collect::<Vec<_>>();
+    imports.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    imports.dedup();
+    imports
+}
+
+/// Collects every string literal from the tool configuration files of a
+/// package (eslint, babel, vite, tsconfig, ...) and the matching package.json
+/// fields. Plugins and presets are usually referenced by name there rather
+/// than imported.
+pub fn scan_config_references(package: &PackageJsonData) -> Result<Vec<String>> {
+    let globs = build_globset(CONFIG_FILE_GLOBS)?;
+    let mut references = Vec::new();
+
+    for entry in fs::read_dir(&package.install_path)?.flatten() {
+        let path = entry.path();
+        if !path.is_file() || !globs.is_match(entry.file_name()) {
+            continue;
+        }
+        debug!("Scanning config file {}", path.display());
+        let Ok(source) = fs::read_to_string(&path) else {
+            continue;
+        };
+        references.extend(
+            STRING_PATTERN
+                .captures_iter(&strip_comments(&source))
+                .map(|captures| captures[1].to_string()),
+        );
+    }
+
+    if let Some((value, _)) = read_package_json(&package.install_path.join("package.json"))? {
+        for field in PACKAGE_JSON_CONFIG_FIELDS {
+            if let Some(config) = value.get(field) {
+                collect_strings(config, &mut references);
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+fn collect_strings(value: &Value, strings: &mut Vec<String>) {
+    match value {
+        Value::String(s) => strings.push(s.clone()),
+        Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(map) => map.iter().for_each(|(k, v)| {
+            strings.push(k.clone());
+            collect_strings(v, strings);
+        }),
+        _ => {}
+    }
+}
+
+/// Removes comments (keeping their newlines so line numbers stay
+/// correct) while leaving string and regex literals alone.
+fn strip_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            result.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    result.push(escaped);
+                }
+            } else if c == q || (c == '\n' && q != '`') {
+                quote = None;
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push('\n');
+                    }
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ('"' | '\'' | '`', _) => {
+                quote = Some(c);
+                result.push(c);
+            }
+            ('/', _) if regex_allowed(&result) => {
+                // Copy the regex literal so `/\/\*/` or `/'/` isn't mistaken
+                // for a comment or string. A `/` in a class like `[/]` doesn't
+                // end it.
+                result.push(c);
+                let mut in_class = false;
+                while let Some(c) = chars.next_if(|&c| c != '\n') {
+                    result.push(c);
+                    match c {
+                        '\\' => result.extend(chars.next_if(|&c| c != '\n')),
+                        '[' => in_class = true,
+                        ']' => in_class = false,
+                        '/' if !in_class => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Whether a `/` after this code starts a regex literal rather than a
+/// division, judging by the token before it
+fn regex_allowed(code: &str) -> bool {
+    let code = code.trim_end();
+    let Some(last) = code.chars().last() else {
+        return true;
+    };
+    if last.is_alphanumeric() || last == '_' || last == '$' {
+        let word = code
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .next()
+            .unwrap_or_default();
+        return REGEX_KEYWORDS.contains(&word);
+    }
+    // `<` before `/` is a JSX closing tag like `</div>`, not a comparison
+    !matches!(last, ')' | ']' | '}' | '"' | '\'' | '`' | '<')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_imports() {
+        let source = r#"
+import React from "react";
+import { a,
+    b } from '@scope/pkg/sub';
+import "./styles.css";
+export * from "re-exported";
+const fs = require("node:fs");
+const lazy = await import('lazy-pkg');
+/// <reference types="vite/client" />
+// import ignored from "commented-out";
+/* require("also-ignored") */
+const url = "http://example.com"; import "after-url";
+"#;
+        let specifiers = scan_imports(source)
+            .into_iter()
+            .map(|(s, line)| format!("{}:{}", s, line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            specifiers,
+            vec![
+                "react:2",
+                "@scope/pkg/sub:4",
+                "./styles.css:5",
+                "re-exported:6",
+                "node:fs:7",
+                "lazy-pkg:8",
+                "vite/client:9",
+                "after-url:12",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strip_comments_keeps_regex_literals() {
+        let source = r#"
+const a = /\/\*/;
+const b = x.replace(/["']/g, "");
+import "kept"; /* removed "x" */
+const c = total / count / 2; import "after-division";
+if (/^\/\//.test(url)) return /[/*]/;
+const el = <div>{label}</div>; import "after-jsx";
+import "last";
+"#;
+        let specifiers = scan_imports(source)
+            .into_iter()
+            .map(|(s, line)| format!("{}:{}", s, line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            specifiers,
+            vec!["kept:4", "after-division:5", "after-jsx:7", "last:8"]
+        );
+    }
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(package_name("react"), Some("react"));
+        assert_eq!(package_name("react-dom/client"), Some("react-dom"));
+        assert_eq!(package_name("@scope/pkg/sub/path"), Some("@scope/pkg"));
+        assert_eq!(package_name("./local"), None);
+        assert_eq!(package_name("/abs/path"), None);
+        assert_eq!(package_name("#internal"), None);
+        assert_eq!(package_name("@/components/Button"), None);
+        assert_eq!(package_name("node:fs"), None);
+        assert_eq!(package_name("fs/promises"), None);
+        assert_eq!(package_name("virtual:pwa-register"), None);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+
+use color_eyre::eyre::{Result, eyre};
+use serde::Serialize;
+
+use crate::{
+    package::{Dependency, Package, PackageEntry},
+    source_scan::{is_node_builtin, package_name, scan_config_references, scan_package},
+};
+
+/// Naming conventions used by tools that load plugins by their short name
+const UNSCOPED_PLUGIN_PREFIXES: &[&str] = &[
+    "eslint-plugin-",
+    "eslint-config-",
+    "babel-plugin-",
+    "babel-preset-",
+    "prettier-plugin-",
+];
+const SCOPED_PLUGIN_PREFIXES: &[&str] = &["eslint-plugin-", "eslint-config-", "plugin-", "preset-"];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UnusedDependencies {
+    pub dependencies: Vec<String>,
+    pub dev_dependencies: Vec<String>,
+}
+
+impl UnusedDependencies {
+    pub fn is_empty(&self) -> bool {
+        self.dependencies.is_empty() && self.dev_dependencies.is_empty()
+    }
+}
+
+/// Finds the declared dependencies of a package that are never imported by
+/// its sources, referenced by its tool configs or run from its scripts.
+pub fn find_unused(package: &Package) -> Result<UnusedDependencies> {
+    let resolver = package
+        .resolver()
+        .ok_or(eyre!("Dependency resolver is missing"))?;
+
+    let mut used = HashSet::new();
+    let mut uses_builtins = false;
+    for import in scan_package(&package.data)? {
+        uses_builtins |= is_node_builtin(&import.specifier);
+        if let Some(name) = import.package_name() {
+            used.insert(name.to_string());
+        }
+    }
+
+    for reference in scan_config_references(&package.data)? {
+        used.extend(config_candidates(&reference));
+    }
+
+    let script_words = package
+        .data
+        .scripts
+        .values()
+        .flat_map(|script| script.split(|c: char| c.is_whitespace() || "&|;()".contains(c)))
+        .filter(|word| !word.is_empty())
+        .collect::<HashSet<_>>();
+    for dependency in package
+        .dependencies
+        .values()
+        .chain(package.dev_dependencies.values())
+    {
+        let bins = match &dependency.package {
+            PackageEntry::Resolved(key) => resolver
+                .get_package(key)
+                .map(|p| p.data.bins.clone())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        if script_words.contains(dependency.name.as_str())
+            || bins.iter().any(|bin| script_words.contains(bin.as_str()))
+        {
+            used.insert(dependency.name.clone());
+        }
+    }
+
+    let is_used = |name: &str| {
+        used.contains(name)
+            || name.strip_prefix("@types/").is_some_and(|typed| {
+                let typed = typed_package_name(typed);
+                used.contains(&typed) || (typed == "node" && uses_builtins)
+            })
+    };
+    let unused = |deps: &HashMap<String, Dependency>| {
+        let mut names = deps
+            .keys()
+            .filter(|name| !is_used(name))
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    Ok(UnusedDependencies {
+        dependencies: unused(&package.dependencies),
+        dev_dependencies: unused(&package.dev_dependencies),
+    })
+}
+
+/// Returns the packages a string in a tool config may refer to, e.g.
+/// `"plugin:react/recommended"` refers to `eslint-plugin-react`.
+fn config_candidates(reference: &str) -> Vec<String> {
+    let reference = reference.strip_prefix("plugin:").unwrap_or(reference);
+    let mut candidates = Vec::new();
+
+    if let Some(name) = package_name(reference) {
+        candidates.push(name.to_string());
+        match name.split_once('/') {
+            Some((scope, short)) => {
+                candidates.push(format!("{}/eslint-plugin", scope));
+                candidates.push(format!("{}/eslint-config", scope));
+                for prefix in SCOPED_PLUGIN_PREFIXES {
+                    candidates.push(format!("{}/{}{}", scope, prefix, short));
+                }
+            }
+            None => {
+                candidates.push(format!("@types/{}", name));
+                for prefix in UNSCOPED_PLUGIN_PREFIXES {
+                    candidates.push(format!("{}{}", prefix, name));
+                }
+            }
+        }
+    } else if reference.len() > 1 && reference.starts_with('@') && !reference.contains('/') {
+        // A bare scope, e.g. `"plugins": ["@typescript-eslint"]`
+        candidates.push(format!("{}/eslint-plugin", reference));
+        candidates.push(format!("{}/eslint-config", reference));
+    }
+
+    candidates
+}
+
+/// Maps the part after `@types/` back to the package it types, e.g.
+/// `babel__core` to `@babel/core`.
+fn typed_package_name(typed: &str) -> String {
+    match typed.split_once("__") {
+        Some((scope, name)) => format!("@{}/{}", scope, name),
+        None => typed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::resolver::Resolver;
+
+    #[test]
+    fn test_find_unused() {
+        let mut resolver = Resolver::new(usize::MAX);
+        let package = resolver.resolve(Path::new("tests/unused")).unwrap();
+
+        // eslint-plugin-react is only referenced from .eslintrc.json, the
+        // @types packages pair with imports (and node with `node:fs`), and
+        // typescript is only run through its `tsc` bin
+        let unused = find_unused(&package).unwrap();
+        assert_eq!(unused.dependencies, vec!["lodash"]);
+        assert_eq!(unused.dev_dependencies, vec!["@types/left-pad", "prettier"]);
+    }
+
+    #[test]
+    fn test_config_candidates() {
+        assert!(
+            config_candidates("plugin:react/recommended").contains(&"eslint-plugin-react".into())
+        );
+        assert!(config_candidates("prettier").contains(&"eslint-config-prettier".into()));
+        assert!(
+            config_candidates("@vitejs/plugin-react-swc")
+                .contains(&"@vitejs/plugin-react-swc".into())
+        );
+        assert!(config_candidates("@babel/env").contains(&"@babel/preset-env".into()));
+        assert!(
+            config_candidates("plugin:@typescript-eslint/recommended")
+                .contains(&"@typescript-eslint/eslint-plugin".into())
+        );
+        assert!(
+            config_candidates("@typescript-eslint")
+                .contains(&"@typescript-eslint/eslint-plugin".into())
+        );
+        assert!(config_candidates("vite/client").contains(&"vite".into()));
+        assert!(config_candidates("**/*.{ts,tsx}").is_empty());
+    }
+
+    #[test]
+    fn test_typed_package_name() {
+        assert_eq!(typed_package_name("react"), "react");
+        assert_eq!(typed_package_name("babel__core"), "@babel/core");
+    }
+}
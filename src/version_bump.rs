@@ -0,0 +1,99 @@
+use std::{cmp::Ordering, fmt};
+
+use colored::*;
+use semver::Version;
+use serde::Serialize;
+
+/// The kind of semver change between two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    Prerelease,
+    Downgrade,
+}
+
+impl Bump {
+    pub const ALL: [Bump; 5] = [
+        Self::Major,
+        Self::Minor,
+        Self::Patch,
+        Self::Prerelease,
+        Self::Downgrade,
+    ];
+
+    /// Classifies a change from `from` to `to`, or None if both have the same
+    /// precedence (build metadata is ignored)
+    pub fn between(from: &Version, to: &Version) -> Option<Self> {
+        match to.cmp_precedence(from) {
+            Ordering::Equal => return None,
+            Ordering::Less => return Some(Self::Downgrade),
+            Ordering::Greater => {}
+        }
+
+        if to.major != from.major {
+            Some(Self::Major)
+        } else if to.minor != from.minor {
+            Some(Self::Minor)
+        } else if to.patch != from.patch {
+            Some(Self::Patch)
+        } else {
+            Some(Self::Prerelease)
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Prerelease => "prerelease",
+            Self::Downgrade => "downgrade",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Major => Color::Red,
+            Self::Minor => Color::Yellow,
+            Self::Patch => Color::Green,
+            Self::Prerelease => Color::Magenta,
+            Self::Downgrade => Color::BrightRed,
+        }
+    }
+
+    /// A badge such as `[MAJOR]` for tree output
+    pub fn badge(&self) -> ColoredString {
+        format!("[{}]", self.as_str().to_uppercase()).color(self.color())
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_between() {
+        let v = |s| Version::parse(s).unwrap();
+        assert_eq!(Bump::between(&v("1.2.3"), &v("2.0.0")), Some(Bump::Major));
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.3.0")), Some(Bump::Minor));
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.2.4")), Some(Bump::Patch));
+        assert_eq!(
+            Bump::between(&v("1.2.3-beta.1"), &v("1.2.3")),
+            Some(Bump::Prerelease)
+        );
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.2.3+build")), None);
+        assert_eq!(
+            Bump::between(&v("2.0.0"), &v("1.2.3")),
+            Some(Bump::Downgrade)
+        );
+    }
+}
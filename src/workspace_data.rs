@@ -37,7 +37,7 @@ impl WorkspaceData {
                     .map(|p| p == path)
                     .unwrap_or(false)
             })
-            .map(|p| p.clone())
+            .cloned()
     }
 }
 
@@ -0,0 +1,8 @@
+import x from "x";
+import missing from "not-installed";
+
+const escaped = /\/\*/;
+export const quote = (s) => s.replace(/["']/g, "");
+
+import { helper } from "y/helper";
+import host from "host";
@@ -0,0 +1,2 @@
+const y = require("y");
+module.exports = y;
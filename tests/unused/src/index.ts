@@ -0,0 +1,3 @@
+import React from "react";
+import { transformSync } from "@babel/core";
+import fs from "node:fs";